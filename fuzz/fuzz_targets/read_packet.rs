@@ -0,0 +1,12 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rust_jarm::{extract_extension_info, read_packet};
+
+// Run with `cargo +nightly fuzz run read_packet`
+fuzz_target!(|data: &[u8]| {
+    let _ = read_packet(data.to_vec());
+    if let Some((counter, data)) = data.split_first() {
+        let _ = extract_extension_info(data.to_vec(), *counter as usize);
+    }
+});
@@ -0,0 +1,53 @@
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use crate::error::JarmError;
+use crate::{resolve, Jarm, Route};
+
+/// Whether the addresses behind a host share the same jarm hash
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Consistency {
+    /// Every address gave the same hash
+    Consistent,
+    /// At least two addresses gave different hashes
+    Inconsistent,
+    /// Some addresses couldn't be fingerprinted, the others gave the same hash
+    Incomplete,
+}
+
+/// Jarm hash of every address a host resolves to
+#[derive(Debug, Default)]
+pub struct AddressHashes {
+    pub hashes: BTreeMap<SocketAddr, Result<String, JarmError>>,
+}
+
+impl AddressHashes {
+    pub fn consistency(&self) -> Consistency {
+        let mut hashes = self.hashes.values().filter_map(|hash| hash.as_ref().ok());
+        let first_hash = hashes.next();
+        if hashes.any(|hash| Some(hash) != first_hash) {
+            Consistency::Inconsistent
+        } else if first_hash.is_none() || self.hashes.values().any(Result::is_err) {
+            Consistency::Incomplete
+        } else {
+            Consistency::Consistent
+        }
+    }
+}
+
+impl Jarm {
+    /// Compute the jarm hash of every IPv4 and IPv6 address the host resolves to.
+    /// Each address gets the full probe queue, the host is still used as SNI.
+    pub fn hash_all_addresses(&self) -> Result<AddressHashes, JarmError> {
+        let Some(spec) = self.queue.first() else {
+            return Ok(AddressHashes::default());
+        };
+        let hashes = resolve(self.resolver.as_ref(), &spec.host, &spec.port)?.into_iter()
+            .map(|address| {
+                let route = Route { address: Some(address), ..self.route() };
+                let pinned_address = route.pin_connection().map(|(pinned_address, _)| pinned_address);
+                (address, self.collect_report_to(&route, self.stops_on_failure(), pinned_address, None).hash())
+            })
+            .collect();
+        Ok(AddressHashes { hashes })
+    }
+}
@@ -0,0 +1,87 @@
+use std::fmt;
+
+pub(crate) const CONTENT_TYPE_ALERT: u8 = 21;
+
+/// TLS alert sent by the server instead of a server hello
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TlsAlert {
+    /// 1 for warning, 2 for fatal
+    pub level: u8,
+    /// Alert description as registered by the IANA, e.g. 40 for `handshake_failure`
+    pub description: u8,
+}
+
+impl TlsAlert {
+    pub fn is_fatal(&self) -> bool {
+        self.level == 2
+    }
+
+    /// Name of the alert level, `unknown` if it isn't a known level
+    pub fn level_name(&self) -> &'static str {
+        match self.level {
+            1 => "warning",
+            2 => "fatal",
+            _ => "unknown",
+        }
+    }
+
+    /// Name of the alert description, `unknown` if it isn't a registered description
+    pub fn description_name(&self) -> &'static str {
+        match self.description {
+            0 => "close_notify",
+            10 => "unexpected_message",
+            20 => "bad_record_mac",
+            21 => "decryption_failed",
+            22 => "record_overflow",
+            30 => "decompression_failure",
+            40 => "handshake_failure",
+            41 => "no_certificate",
+            42 => "bad_certificate",
+            43 => "unsupported_certificate",
+            44 => "certificate_revoked",
+            45 => "certificate_expired",
+            46 => "certificate_unknown",
+            47 => "illegal_parameter",
+            48 => "unknown_ca",
+            49 => "access_denied",
+            50 => "decode_error",
+            51 => "decrypt_error",
+            60 => "export_restriction",
+            70 => "protocol_version",
+            71 => "insufficient_security",
+            80 => "internal_error",
+            86 => "inappropriate_fallback",
+            90 => "user_canceled",
+            100 => "no_renegotiation",
+            109 => "missing_extension",
+            110 => "unsupported_extension",
+            111 => "certificate_unobtainable",
+            112 => "unrecognized_name",
+            113 => "bad_certificate_status_response",
+            114 => "bad_certificate_hash_value",
+            115 => "unknown_psk_identity",
+            116 => "certificate_required",
+            120 => "no_application_protocol",
+            121 => "ech_required",
+            _ => "unknown",
+        }
+    }
+}
+
+impl fmt::Display for TlsAlert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} alert {} ({})", self.level_name(), self.description_name(), self.description)
+    }
+}
+
+/// Read the alert of the server response, if it starts with an alert record
+pub fn read_alert(data: &[u8]) -> Option<TlsAlert> {
+    if *data.first()? != CONTENT_TYPE_ALERT {
+        return None;
+    }
+    let length = ((*data.get(3)? as usize) << 8) + *data.get(4)? as usize;
+    if length < 2 {
+        return None;
+    }
+    Some(TlsAlert { level: *data.get(5)?, description: *data.get(6)? })
+}
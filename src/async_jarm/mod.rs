@@ -0,0 +1,214 @@
+use std::future::Future;
+use std::io::{self, ErrorKind};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::task::{spawn_blocking, JoinSet};
+use tokio::time::timeout;
+use crate::error::{DetailedError, JarmError};
+use crate::connect::sort_addresses;
+use crate::proxy::Proxy;
+use crate::report::{JarmReport, ProbeOutcome, ProbeReport};
+use crate::resolver::dns_error;
+use crate::response::read_response_async;
+use crate::transport::{AsyncJarmStream, JarmTransport};
+use crate::{build_packet, resolve, timed_out, Jarm, JarmPart, ProbeResult, Route};
+
+impl Jarm {
+    /// Async counterpart of `retrieve_parts`.
+    /// The ten client hellos are sent concurrently, each over its own connection.
+    /// Packets are built upfront with `self.rng` so the returned future is `Send` and `'static`.
+    pub fn retrieve_parts_async(&self) -> impl Future<Output = Result<Vec<JarmPart>, JarmError>> + Send + 'static {
+        let probes = self.retrieve_probes_async();
+        async move {
+            Ok(probes.await?.into_iter().map(|probe| probe.part).collect())
+        }
+    }
+
+    /// Async counterpart of `retrieve_probes`, see `retrieve_parts_async`
+    pub fn retrieve_probes_async(&self) -> impl Future<Output = Result<Vec<ProbeResult>, JarmError>> + Send + 'static {
+        let report = self.report_async();
+        async move {
+            report.await.into_probes()
+        }
+    }
+
+    /// Async counterpart of `report`, see `retrieve_parts_async`.
+    /// Connections are opened with `JarmTransport::connect_async`, only the resolver runs on the blocking threads.
+    pub fn report_async(&self) -> impl Future<Output = JarmReport> + Send + 'static {
+        let payloads: Vec<Vec<u8>> = self.queue.iter().map(|spec| build_packet(spec, self.rng.as_ref())).collect();
+        let route = Arc::new(self.route());
+        let failure_policy = self.failure_policy;
+
+        async move {
+            let (address, mut connection) = match route.pin_connection_async().await {
+                Ok(pinned) => pinned,
+                Err(e) => {
+                    // Only the first probe gets the resolution error, the others can't be sent either
+                    let name = match &e {
+                        JarmError::DnsResolve { name, .. } => name.clone(),
+                        _ => String::new(),
+                    };
+                    let mut error = Some(e);
+                    let probes = payloads.iter().map(|_| {
+                        let error = error.take().unwrap_or_else(|| dns_error(&name, DetailedError::default()));
+                        ProbeReport::failed(None, None, ProbeOutcome::Failed, error)
+                    }).collect();
+                    return JarmReport { probes, address: None, failure_policy };
+                }
+            };
+
+            let mut tasks = JoinSet::new();
+            for (index, payload) in payloads.into_iter().enumerate() {
+                let connection = connection.take();
+                let route = Arc::clone(&route);
+                tasks.spawn(async move {
+                    let report = match connection {
+                        Some(connection) => match route.probe_timeout() {
+                            Some(timeout) => exchange_async(connection, &payload, timeout).await,
+                            None => timed_out(address, Some(connection.connect_time)),
+                        },
+                        None => route.send_probe_async(address, &payload).await,
+                    };
+                    (index, report)
+                });
+            }
+
+            let mut reports: Vec<Option<ProbeReport>> = Vec::new();
+            reports.resize_with(tasks.len(), || None);
+            while let Some(joined) = tasks.join_next().await {
+                match joined {
+                    Ok((index, report)) => reports[index] = Some(report),
+                    Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+                    Err(_) => {},  // cancelled by the runtime shutting down
+                }
+            }
+            let probes = reports.into_iter().map(|report| report.unwrap_or_else(|| {
+                let error = JarmError::Io(DetailedError::from(Box::from("probe cancelled")));
+                ProbeReport::failed(None, None, ProbeOutcome::Failed, error)
+            })).collect();
+            JarmReport { probes, address: Some(address), failure_policy }
+        }
+    }
+
+    /// Async counterpart of `hash`, the result is identical for the same server responses.
+    pub async fn hash_async(&mut self) -> Result<String, JarmError> {
+        if self.parts.is_empty() {
+            self.parts = self.retrieve_parts_async().await?
+        }
+        self.compute_hash()
+    }
+}
+
+/// Async counterpart of `Connection`
+struct AsyncConnection {
+    address: SocketAddr,
+    stream: Box<dyn AsyncJarmStream>,
+    connect_time: Duration,
+}
+
+impl Route {
+    /// Async counterpart of `pin_connection`, the resolver is blocking so it runs on the blocking threads
+    async fn pin_connection_async(&self) -> Result<(SocketAddr, Option<AsyncConnection>), JarmError> {
+        match (&self.proxy, self.address) {
+            (Some(proxy), _) => return Ok((proxy.address(), None)),
+            (None, Some(address)) => return Ok((address, None)),
+            (None, None) => {},
+        }
+        let (resolver, host, port) = (Arc::clone(&self.resolver), self.host.clone(), self.port.clone());
+        let resolved = match spawn_blocking(move || resolve(resolver.as_ref(), &host, &port)).await {
+            Ok(resolved) => resolved?,
+            Err(e) => return Err(dns_error(&self.host, DetailedError::from(Box::from(e)))),
+        };
+        let addresses = sort_addresses(&resolved, self.address_preference);
+        match addresses.as_slice() {
+            [] => Err(dns_error(&self.host, DetailedError::default())),
+            [address] => Ok((*address, None)),
+            [first, ..] => {
+                let connection = match self.probe_timeout() {
+                    Some(timeout) => connect_first_async(&self.transport, &addresses, self.attempt_delay, timeout).await,
+                    None => None,
+                };
+                match connection {
+                    Some(connection) => Ok((connection.address, Some(connection))),
+                    None => Ok((*first, None)),
+                }
+            },
+        }
+    }
+
+    /// Async counterpart of `send_probe`
+    async fn send_probe_async(&self, address: SocketAddr, payload: &[u8]) -> ProbeReport {
+        let Some(timeout) = self.probe_timeout() else {
+            return timed_out(address, None);
+        };
+        let start = Instant::now();
+        let stream = match &self.proxy {
+            Some(proxy) => self.connect_through_async(proxy, timeout).await,
+            None => self.transport.connect_async(address, timeout).await
+                .map_err(|e| (ProbeOutcome::from(&e), JarmError::Connection(DetailedError::from(Box::from(e))))),
+        };
+        let connect_time = start.elapsed();
+        match (stream, self.probe_timeout()) {
+            (Ok(stream), Some(timeout)) => exchange_async(AsyncConnection { address, stream, connect_time }, payload, timeout).await,
+            (Ok(_), None) => timed_out(address, Some(connect_time)),
+            (Err((outcome, error)), _) => ProbeReport::failed(Some(address), None, outcome, error),
+        }
+    }
+
+    /// Async counterpart of `connect_through`
+    async fn connect_through_async(&self, proxy: &Proxy, timeout: Duration) -> Result<Box<dyn AsyncJarmStream>, (ProbeOutcome, JarmError)> {
+        let (host, port) = self.tunnel_target()?;
+        proxy.connect_async(self.transport.as_ref(), &host, port, timeout).await.map_err(|e| (ProbeOutcome::Failed, e))
+    }
+}
+
+/// Async counterpart of `connect_first`, attempts being tasks instead of threads
+async fn connect_first_async(transport: &Arc<dyn JarmTransport>, addresses: &[SocketAddr], attempt_delay: Duration, connect_timeout: Duration) -> Option<AsyncConnection> {
+    // The attempts left are aborted when the set is dropped
+    let mut attempts = JoinSet::new();
+    let mut next = 0;
+    loop {
+        if let Some(&address) = addresses.get(next) {
+            let transport = Arc::clone(transport);
+            attempts.spawn(async move {
+                let start = Instant::now();
+                let result = transport.connect_async(address, connect_timeout).await;
+                (address, result, start.elapsed())
+            });
+            next += 1;
+        }
+
+        let joined = if next < addresses.len() {
+            match timeout(attempt_delay, attempts.join_next()).await {
+                Ok(joined) => joined,
+                Err(_) => continue,  // time for the next attempt
+            }
+        } else {
+            attempts.join_next().await
+        };
+
+        match joined {
+            Some(Ok((address, Ok(stream), connect_time))) => return Some(AsyncConnection { address, stream, connect_time }),
+            Some(_) => {},
+            None if next < addresses.len() => {},
+            None => return None,  // every attempt failed
+        }
+    }
+}
+
+/// Async counterpart of `exchange`
+async fn exchange_async(connection: AsyncConnection, payload: &[u8], probe_timeout: Duration) -> ProbeReport {
+    let AsyncConnection { address, mut stream, connect_time } = connection;
+    let exchange = async {
+        timeout(probe_timeout, stream.write_all(payload)).await
+            .unwrap_or_else(|_| Err(io::Error::from(ErrorKind::TimedOut)))?;
+        let sent = Instant::now();
+        Ok::<_, io::Error>((sent, read_response_async(stream.as_mut(), probe_timeout).await?))
+    };
+    match exchange.await {
+        Ok((sent, response)) => ProbeReport::answered(address, connect_time, sent, response),
+        Err(e) => ProbeReport::failed(Some(address), Some(connect_time), ProbeOutcome::from(&e), e.into()),
+    }
+}
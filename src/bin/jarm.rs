@@ -0,0 +1,171 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::Duration;
+use clap::Parser;
+use rust_jarm::Jarm;
+use rust_jarm::error::{DetailedError, JarmError};
+use rust_jarm::proxy::Proxy;
+use rust_jarm::sni::ServerName;
+
+/// Command line equivalent of salesforce/jarm jarm.py
+#[derive(Parser)]
+#[command(name = "jarm", version, about = "Compute the JARM fingerprint of TLS servers")]
+struct Args {
+    /// IP or domain to scan
+    #[arg(required_unless_present = "input", conflicts_with = "input")]
+    scan: Option<String>,
+
+    /// File of IPs or domains to scan, one per line. A port can be given after a comma (e.g. 8.8.4.4,853)
+    #[arg(short, long)]
+    input: Option<PathBuf>,
+
+    /// Port to scan
+    #[arg(short, long, default_value_t = 443)]
+    port: u16,
+
+    /// Display the JARM results before being hashed
+    #[arg(short, long)]
+    verbose: bool,
+
+    /// CSV file to append the results to
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Timeout of each connection, in seconds
+    #[arg(short, long, default_value_t = 20)]
+    timeout: u64,
+
+    /// Proxy to scan through, as ip:port for SOCKS5 or a URL such as http://user:password@ip:port
+    #[arg(short = 'P', long, value_parser = parse_proxy)]
+    proxy: Option<Proxy>,
+
+    /// Server name to send in the SNI extension instead of the scanned host
+    #[arg(long, conflicts_with = "no_sni", value_parser = parse_server_name)]
+    sni: Option<ServerName>,
+
+    /// Don't send the SNI extension
+    #[arg(long)]
+    no_sni: bool,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let targets = match &args.input {
+        Some(path) => match fs::read_to_string(path) {
+            Ok(content) => parse_targets(&content, args.port),
+            Err(e) => {
+                eprintln!("Failed to read {}: {e}", path.display());
+                return ExitCode::FAILURE;
+            }
+        },
+        None => vec![(args.scan.clone().unwrap_or_default(), args.port)],
+    };
+
+    let mut output = match &args.output {
+        Some(path) => match OpenOptions::new().create(true).append(true).open(path) {
+            Ok(file) => Some(file),
+            Err(e) => {
+                eprintln!("Failed to open {}: {e}", path.display());
+                return ExitCode::FAILURE;
+            }
+        },
+        None => None,
+    };
+
+    for (host, port) in targets {
+        let result = scan(&host, port, &args);
+        if let Some(file) = output.as_mut()
+            && let Err(e) = writeln!(file, "{host},{},{}", result.ip, result.hash) {
+            eprintln!("Failed to write the results: {e}");
+            return ExitCode::FAILURE;
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+struct ScanResult {
+    ip: String,
+    hash: String,
+}
+
+fn scan(host: &str, port: u16, args: &Args) -> ScanResult {
+    let mut jarm = Jarm::new(host.to_string(), port.to_string());
+    jarm.timeout = Duration::from_secs(args.timeout);
+    jarm.proxy = args.proxy.clone();
+    let server_name = match (&args.sni, args.no_sni) {
+        (Some(server_name), _) => Some(server_name.clone()),
+        (None, true) => Some(ServerName::Omitted),
+        (None, false) => None,
+    };
+    if let Some(server_name) = server_name {
+        jarm.set_server_name(server_name).expect("server name checked when parsing the arguments");
+    }
+    let report = jarm.report();
+    // Through a proxy, the host is resolved by the proxy and the reported address is the proxy's
+    let ip = match (&args.proxy, report.address) {
+        (None, Some(address)) => address.ip().to_string(),
+        _ => String::new(),
+    };
+    let mut probes = Vec::new();
+    let hash = match report.into_probes().and_then(|results| {
+        jarm.parts = results.iter().map(|probe| probe.part.clone()).collect();
+        probes = results;
+        jarm.hash()
+    }) {
+        Ok(hash) => hash,
+        Err(e) => {
+            eprintln!("Failed to scan {host}:{port}: {e:?}");
+            "0".repeat(62)  // same as a server not answering any probe
+        }
+    };
+
+    println!("Domain: {host}");
+    println!("Resolved IP: {ip}");
+    if args.verbose {
+        for (index, probe) in probes.iter().enumerate() {
+            match &probe.alert {
+                Some(alert) => println!("Scan {}: {} ({alert})", index + 1, probe.part),
+                None => println!("Scan {}: {}", index + 1, probe.part),
+            }
+        }
+    }
+    println!("JARM: {hash}");
+    ScanResult { ip, hash }
+}
+
+fn parse_server_name(value: &str) -> Result<ServerName, String> {
+    let server_name = ServerName::Name(value.to_string());
+    match server_name.validate() {
+        Ok(()) => Ok(server_name),
+        Err(JarmError::InvalidTarget { reason, .. }) => Err(reason.to_string()),
+        Err(e) => Err(format!("{e:?}")),
+    }
+}
+
+fn parse_proxy(value: &str) -> Result<Proxy, String> {
+    value.parse().map_err(|e| match e {
+        JarmError::Proxy(DetailedError { underlying_error: Some(error) }) => error.to_string(),
+        e => format!("{e:?}"),
+    })
+}
+
+/// Parse the input file, each line being `host` or `host,port`
+fn parse_targets(content: &str, default_port: u16) -> Vec<(String, u16)> {
+    content.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .filter_map(|line| match line.split_once(',') {
+            None => Some((line.to_string(), default_port)),
+            Some((host, port)) => match port.trim().parse() {
+                Ok(port) => Some((host.trim().to_string(), port)),
+                Err(_) => {
+                    eprintln!("Skipping {line:?}: invalid port");
+                    None
+                }
+            },
+        })
+        .collect()
+}
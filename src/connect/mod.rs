@@ -0,0 +1,143 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::transport::{JarmStream, JarmTransport};
+
+/// Address family tried first when a host resolves to several addresses, see RFC 8305
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AddressPreference {
+    /// Start with the family of the first address given by the resolver
+    #[default]
+    Resolver,
+    Ipv6,
+    Ipv4,
+}
+
+/// Open connection to one of the resolved addresses
+pub(crate) struct Connection {
+    pub address: SocketAddr,
+    pub stream: Box<dyn JarmStream>,
+    pub connect_time: Duration,
+}
+
+/// Interleave the IPv6 and IPv4 addresses, starting with the preferred family.
+/// The order of the resolver is kept within each family.
+pub(crate) fn sort_addresses(addresses: &[SocketAddr], preference: AddressPreference) -> Vec<SocketAddr> {
+    let prefer_ipv6 = match preference {
+        AddressPreference::Resolver => addresses.first().is_some_and(SocketAddr::is_ipv6),
+        AddressPreference::Ipv6 => true,
+        AddressPreference::Ipv4 => false,
+    };
+    let (preferred, others): (Vec<SocketAddr>, Vec<SocketAddr>) = addresses.iter()
+        .partition(|address| address.is_ipv6() == prefer_ipv6);
+
+    let mut sorted = Vec::with_capacity(addresses.len());
+    let (mut preferred, mut others) = (preferred.into_iter(), others.into_iter());
+    loop {
+        match (preferred.next(), others.next()) {
+            (None, None) => break,
+            (first, second) => sorted.extend(first.into_iter().chain(second)),
+        }
+    }
+    sorted
+}
+
+/// Connect to the first address accepting the connection.
+/// A new attempt starts every `attempt_delay`, or as soon as all the previous ones failed.
+pub(crate) fn connect_first(transport: &Arc<dyn JarmTransport>, addresses: &[SocketAddr], attempt_delay: Duration, timeout: Duration) -> Option<Connection> {
+    let (sender, receiver) = channel();
+    let mut next = 0;
+    let mut pending = 0;
+    loop {
+        if let Some(&address) = addresses.get(next) {
+            let (sender, transport) = (sender.clone(), Arc::clone(transport));
+            thread::spawn(move || {
+                let start = Instant::now();
+                let result = transport.connect(address, timeout);
+                // The connection is dropped if another attempt already won
+                let _ = sender.send((address, result, start.elapsed()));
+            });
+            next += 1;
+            pending += 1;
+        }
+
+        let attempt = if next < addresses.len() {
+            match receiver.recv_timeout(attempt_delay) {
+                Ok(attempt) => attempt,
+                Err(RecvTimeoutError::Timeout) => continue,  // time for the next attempt
+                Err(RecvTimeoutError::Disconnected) => return None,
+            }
+        } else if pending > 0 {
+            match receiver.recv() {
+                Ok(attempt) => attempt,
+                Err(_) => return None,
+            }
+        } else {
+            return None;  // every attempt failed
+        };
+
+        pending -= 1;
+        if let (address, Ok(stream), connect_time) = attempt {
+            return Some(Connection { address, stream, connect_time });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{SocketAddr, TcpListener};
+    use std::sync::Arc;
+    use std::time::Duration;
+    use crate::transport::{JarmTransport, TcpTransport};
+    use super::{connect_first, sort_addresses, AddressPreference};
+
+    fn addresses(raw: &[&str]) -> Vec<SocketAddr> {
+        raw.iter().map(|address| address.parse().unwrap()).collect()
+    }
+
+    fn tcp() -> Arc<dyn JarmTransport> {
+        Arc::new(TcpTransport)
+    }
+
+    #[test]
+    fn test_sort_addresses_interleaves_families() {
+        let resolved = addresses(&["10.0.0.1:443", "10.0.0.2:443", "[2001:db8::1]:443", "10.0.0.3:443", "[2001:db8::2]:443"]);
+
+        assert_eq!(
+            sort_addresses(&resolved, AddressPreference::Resolver),
+            addresses(&["10.0.0.1:443", "[2001:db8::1]:443", "10.0.0.2:443", "[2001:db8::2]:443", "10.0.0.3:443"]),
+        );
+        assert_eq!(
+            sort_addresses(&resolved, AddressPreference::Ipv6),
+            addresses(&["[2001:db8::1]:443", "10.0.0.1:443", "[2001:db8::2]:443", "10.0.0.2:443", "10.0.0.3:443"]),
+        );
+        assert_eq!(sort_addresses(&resolved, AddressPreference::Ipv4), sort_addresses(&resolved, AddressPreference::Resolver));
+    }
+
+    #[test]
+    fn test_connect_first_skips_failed_addresses() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let listening = listener.local_addr().unwrap();
+        let closed = {
+            let listener = TcpListener::bind("127.0.0.2:0").unwrap();
+            listener.local_addr().unwrap()
+        };
+
+        // A long delay, the next attempt must start as soon as the first one fails
+        let connection = connect_first(&tcp(), &[closed, listening], Duration::from_secs(30), Duration::from_secs(2)).unwrap();
+
+        assert_eq!(connection.address, listening);
+    }
+
+    #[test]
+    fn test_connect_first_all_failed() {
+        let closed = {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap()
+        };
+
+        assert!(connect_first(&tcp(), &[closed, closed], Duration::from_millis(10), Duration::from_secs(2)).is_none());
+    }
+}
@@ -2,15 +2,30 @@ use std::error::Error;
 
 #[derive(Debug)]
 pub enum JarmError {
-    DnsResolve(DetailedError),
+    /// The host can't be resolved, `name` is the queried host
+    DnsResolve { name: String, error: DetailedError },
     Connection(DetailedError),
+    /// The proxy couldn't be reached or refused to open the connection to the server
+    Proxy(DetailedError),
     Io(DetailedError),
+    Capture(DetailedError),
+    MalformedPart(DetailedError),
+    /// The server response can't be parsed, `offset` is the position of the faulty byte
+    Parse { offset: usize, reason: &'static str },
+    /// The target to scan is not a valid host and port, `target` is the given value
+    InvalidTarget { target: String, reason: &'static str },
+    /// The value is not a jarm hash, `hash` is the given value
+    InvalidHash { hash: String, reason: &'static str },
+    /// A serialized hash index can't be read, `offset` is the position of the faulty byte
+    HashIndex { offset: usize, reason: &'static str },
+    /// An nmap or masscan output can't be parsed, `offset` is the position of the faulty byte
+    ScanOutput { offset: usize, reason: &'static str },
 }
 
 
 #[derive(Debug, Default)]
 pub struct DetailedError {
-    pub underlying_error: Option<Box<dyn Error>>,
+    pub underlying_error: Option<Box<dyn Error + Send + Sync>>,
 }
 
 impl From<std::io::Error> for JarmError {
@@ -22,8 +37,8 @@ impl From<std::io::Error> for JarmError {
 }
 
 
-impl From<Box<dyn Error>> for DetailedError {
-    fn from(error: Box<dyn Error>) -> Self {
+impl From<Box<dyn Error + Send + Sync>> for DetailedError {
+    fn from(error: Box<dyn Error + Send + Sync>) -> Self {
         DetailedError { underlying_error: Some(error) }
     }
 }
@@ -0,0 +1,327 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::ops::RangeInclusive;
+use std::path::Path;
+use crate::error::JarmError;
+use crate::sni::ServerName;
+use crate::target::{validate_host_name, Target, TargetHost, DEFAULT_PORT};
+
+const ALL_PORTS: RangeInclusive<u16> = 1..=u16::MAX;
+
+/// Expand patterns such as `10.0.0.0/24`, `host:443,8443` or `1.2.3.4:8000-8010` into targets.
+/// The targets are generated lazily, a pattern covering a huge range only holds its bounds.
+#[derive(Debug, Clone)]
+pub struct TargetExpander {
+    /// Port of the patterns without any
+    pub default_port: u16,
+    excluded: Vec<TargetPattern>,
+}
+
+impl Default for TargetExpander {
+    fn default() -> Self {
+        TargetExpander { default_port: DEFAULT_PORT, excluded: Vec::new() }
+    }
+}
+
+impl TargetExpander {
+    pub fn new() -> TargetExpander {
+        TargetExpander::default()
+    }
+
+    /// Skip the targets matching `pattern`, on every port if the pattern has none
+    pub fn exclude(mut self, pattern: &str) -> Result<TargetExpander, JarmError> {
+        self.excluded.push(TargetPattern::parse(pattern, ALL_PORTS)?);
+        Ok(self)
+    }
+
+    /// Targets of every input, each one holding patterns separated by whitespaces.
+    /// Empty inputs and comments starting with `#` are skipped, invalid patterns are yielded as errors.
+    pub fn expand<'a, I, S>(&self, inputs: I) -> ExpandedTargets<'a>
+    where
+        I: IntoIterator<Item = S>,
+        I::IntoIter: Send + 'a,
+        S: AsRef<str>,
+    {
+        let lines = inputs.into_iter().map(|input| Ok(input.as_ref().to_string()));
+        ExpandedTargets::new(self, Box::new(lines))
+    }
+
+    /// Targets of a file of patterns, read line by line as the targets are consumed
+    pub fn expand_file<P: AsRef<Path>>(&self, path: P) -> Result<ExpandedTargets<'static>, JarmError> {
+        let lines = BufReader::new(File::open(path)?).lines()
+            .map(|line| line.map_err(JarmError::from));
+        Ok(ExpandedTargets::new(self, Box::new(lines)))
+    }
+}
+
+/// Hosts covered by a pattern, IP ranges being stored as integers
+#[derive(Debug, Clone)]
+enum Hosts {
+    Name(String),
+    Ipv4(RangeInclusive<u32>),
+    Ipv6(RangeInclusive<u128>),
+}
+
+impl Hosts {
+    fn first(&self) -> u128 {
+        match self {
+            Hosts::Name(_) => 0,
+            Hosts::Ipv4(range) => *range.start() as u128,
+            Hosts::Ipv6(range) => *range.start(),
+        }
+    }
+
+    fn last(&self) -> u128 {
+        match self {
+            Hosts::Name(_) => 0,
+            Hosts::Ipv4(range) => *range.end() as u128,
+            Hosts::Ipv6(range) => *range.end(),
+        }
+    }
+
+    fn host(&self, index: u128) -> TargetHost {
+        match self {
+            Hosts::Name(name) => TargetHost::Name(name.clone()),
+            Hosts::Ipv4(_) => TargetHost::Ip(IpAddr::V4(Ipv4Addr::from(index as u32))),
+            Hosts::Ipv6(_) => TargetHost::Ip(IpAddr::V6(Ipv6Addr::from(index))),
+        }
+    }
+
+    /// The host of a single host pattern, which is looked up faster than a range
+    fn single(&self) -> Option<TargetHost> {
+        match self {
+            Hosts::Name(name) => Some(TargetHost::Name(name.to_ascii_lowercase())),
+            Hosts::Ipv4(range) if range.start() == range.end() => Some(self.host(*range.start() as u128)),
+            Hosts::Ipv6(range) if range.start() == range.end() => Some(self.host(*range.start())),
+            _ => None,
+        }
+    }
+
+    fn contains(&self, host: &TargetHost) -> bool {
+        match (self, host) {
+            (Hosts::Name(name), TargetHost::Name(other)) => name.eq_ignore_ascii_case(other),
+            (Hosts::Ipv4(range), TargetHost::Ip(IpAddr::V4(ip))) => range.contains(&u32::from(*ip)),
+            (Hosts::Ipv6(range), TargetHost::Ip(IpAddr::V6(ip))) => range.contains(&u128::from(*ip)),
+            _ => false,
+        }
+    }
+}
+
+/// Parsed pattern: hosts and sorted, non overlapping port ranges
+#[derive(Debug, Clone)]
+struct TargetPattern {
+    hosts: Hosts,
+    ports: Vec<RangeInclusive<u16>>,
+}
+
+impl TargetPattern {
+    /// Parse `hosts[:ports]`, an IPv6 network needing brackets when ports are given
+    fn parse(pattern: &str, default_ports: RangeInclusive<u16>) -> Result<TargetPattern, JarmError> {
+        let invalid = |reason| JarmError::InvalidTarget { target: pattern.to_string(), reason };
+        let (hosts, ports) = match pattern.strip_prefix('[') {
+            Some(bracketed) => match bracketed.split_once(']') {
+                Some((hosts, "")) => (hosts, None),
+                Some((hosts, ports)) => (hosts, Some(ports.strip_prefix(':').ok_or_else(|| invalid("unexpected characters after ']'"))?)),
+                None => return Err(invalid("missing closing bracket")),
+            },
+            // Bare IPv6 address or network, without ports
+            None if pattern.matches(':').count() > 1 => (pattern, None),
+            None => match pattern.rsplit_once(':') {
+                Some((hosts, ports)) => (hosts, Some(ports)),
+                None => (pattern, None),
+            },
+        };
+        let hosts = parse_hosts(hosts).map_err(invalid)?;
+        let ports = match ports {
+            None => vec![default_ports],
+            Some(ports) => parse_ports(ports).map_err(invalid)?,
+        };
+        Ok(TargetPattern { hosts, ports })
+    }
+
+    fn contains(&self, target: &Target) -> bool {
+        self.hosts.contains(&target.host) && self.ports.iter().any(|ports| ports.contains(&target.port))
+    }
+}
+
+fn parse_hosts(hosts: &str) -> Result<Hosts, &'static str> {
+    let Some((address, prefix)) = hosts.split_once('/') else {
+        return match hosts.parse::<IpAddr>() {
+            Ok(IpAddr::V4(ip)) => Ok(Hosts::Ipv4(u32::from(ip)..=u32::from(ip))),
+            Ok(IpAddr::V6(ip)) => Ok(Hosts::Ipv6(u128::from(ip)..=u128::from(ip))),
+            Err(_) => Ok(Hosts::Name(validate_host_name(hosts)?.to_string())),
+        };
+    };
+    let prefix: u32 = prefix.parse().map_err(|_| "invalid network prefix length")?;
+    match address.parse::<IpAddr>().map_err(|_| "invalid network address")? {
+        IpAddr::V4(ip) if prefix <= 32 => {
+            let mask = u32::MAX.checked_shl(32 - prefix).unwrap_or(0);
+            let first = u32::from(ip) & mask;
+            Ok(Hosts::Ipv4(first..=first | !mask))
+        },
+        IpAddr::V6(ip) if prefix <= 128 => {
+            let mask = u128::MAX.checked_shl(128 - prefix).unwrap_or(0);
+            let first = u128::from(ip) & mask;
+            Ok(Hosts::Ipv6(first..=first | !mask))
+        },
+        _ => Err("network prefix length too long"),
+    }
+}
+
+/// Parse a list such as `443,8000-8010`, the ranges are sorted and merged
+fn parse_ports(ports: &str) -> Result<Vec<RangeInclusive<u16>>, &'static str> {
+    let parse_port = |port: &str| match port.trim().parse::<u16>() {
+        Ok(0) | Err(_) => Err("the port is not a number between 1 and 65535"),
+        Ok(port) => Ok(port),
+    };
+    let mut ranges = Vec::new();
+    for range in ports.split(',') {
+        let range = match range.split_once('-') {
+            Some((start, end)) => parse_port(start)?..=parse_port(end)?,
+            None => parse_port(range)?..=parse_port(range)?,
+        };
+        if range.is_empty() {
+            return Err("port range ending before its start");
+        }
+        ranges.push(range);
+    }
+    ranges.sort_by_key(|range| *range.start());
+
+    let mut merged: Vec<RangeInclusive<u16>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if *range.start() <= last.end().saturating_add(1) => {
+                *last = *last.start()..=*last.end().max(range.end());
+            },
+            _ => merged.push(range),
+        }
+    }
+    Ok(merged)
+}
+
+/// Position in the targets of one pattern, hosts being the outer loop
+struct PatternCursor {
+    pattern: TargetPattern,
+    host: Option<u128>,
+    range: usize,
+    port: u32,
+}
+
+impl PatternCursor {
+    fn new(pattern: TargetPattern) -> PatternCursor {
+        let host = Some(pattern.hosts.first());
+        let port = pattern.ports.first().map_or(0, |range| *range.start() as u32);
+        PatternCursor { pattern, host, range: 0, port }
+    }
+}
+
+impl Iterator for PatternCursor {
+    type Item = Target;
+
+    fn next(&mut self) -> Option<Target> {
+        loop {
+            let host = self.host?;
+            if let Some(range) = self.pattern.ports.get(self.range) {
+                if self.port > *range.end() as u32 {
+                    self.range += 1;
+                    self.port = self.pattern.ports.get(self.range).map_or(0, |range| *range.start() as u32);
+                    continue;
+                }
+                let port = self.port as u16;
+                self.port += 1;
+                return Some(Target {
+                    host: self.pattern.hosts.host(host),
+                    port,
+                    server_name: ServerName::default(),
+                    address: None,
+                });
+            }
+            // Every port of this host was yielded
+            self.host = (host < self.pattern.hosts.last()).then(|| host + 1);
+            self.range = 0;
+            self.port = self.pattern.ports.first().map_or(0, |range| *range.start() as u32);
+        }
+    }
+}
+
+/// Lazy iterator over the expanded targets, see `TargetExpander`.
+/// A target already yielded by a previous pattern or matching an exclusion is skipped.
+pub struct ExpandedTargets<'a> {
+    lines: Box<dyn Iterator<Item = Result<String, JarmError>> + Send + 'a>,
+    default_port: u16,
+    excluded: Vec<TargetPattern>,
+    /// Ports already yielded for hosts given alone, e.g. `host:443`
+    previous_hosts: HashMap<TargetHost, Vec<RangeInclusive<u16>>>,
+    /// Networks already yielded
+    previous_networks: Vec<TargetPattern>,
+    pending: Vec<String>,
+    current: Option<PatternCursor>,
+}
+
+impl<'a> ExpandedTargets<'a> {
+    fn new(expander: &TargetExpander, lines: Box<dyn Iterator<Item = Result<String, JarmError>> + Send + 'a>) -> ExpandedTargets<'a> {
+        ExpandedTargets {
+            lines,
+            default_port: expander.default_port,
+            excluded: expander.excluded.clone(),
+            previous_hosts: HashMap::new(),
+            previous_networks: Vec::new(),
+            pending: Vec::new(),
+            current: None,
+        }
+    }
+
+    fn is_skipped(&self, target: &Target) -> bool {
+        let host = match &target.host {
+            TargetHost::Name(name) => TargetHost::Name(name.to_ascii_lowercase()),
+            host => host.clone(),
+        };
+        self.previous_hosts.get(&host).is_some_and(|ports| ports.iter().any(|ports| ports.contains(&target.port)))
+            || self.excluded.iter().chain(&self.previous_networks).any(|pattern| pattern.contains(target))
+    }
+
+    /// Next pattern to expand, reading a new line once the patterns of the previous one are done
+    fn next_pattern(&mut self) -> Option<Result<TargetPattern, JarmError>> {
+        loop {
+            if let Some(pattern) = self.pending.pop() {
+                return Some(TargetPattern::parse(&pattern, self.default_port..=self.default_port));
+            }
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+            let line = line.split('#').next().unwrap_or_default();
+            self.pending = line.split_whitespace().rev().map(str::to_string).collect();
+        }
+    }
+}
+
+impl Iterator for ExpandedTargets<'_> {
+    type Item = Result<Target, JarmError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(cursor) = self.current.as_mut() {
+                match cursor.next() {
+                    Some(target) if self.is_skipped(&target) => continue,
+                    Some(target) => return Some(Ok(target)),
+                    None => {
+                        // Done, its targets are now skipped by the next patterns
+                        if let Some(cursor) = self.current.take() {
+                            match cursor.pattern.hosts.single() {
+                                Some(host) => self.previous_hosts.entry(host).or_default().extend(cursor.pattern.ports),
+                                None => self.previous_networks.push(cursor.pattern),
+                            }
+                        }
+                    },
+                }
+            }
+            match self.next_pattern()? {
+                Ok(pattern) => self.current = Some(PatternCursor::new(pattern)),
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::fmt;
+use std::str::FromStr;
+use crate::error::JarmError;
+use crate::JARM_CIPHERS;
+
+/// Number of probes summarised in a jarm hash
+pub const PROBE_COUNT: usize = 10;
+const FUZZY_HASH_LENGTH: usize = 3 * PROBE_COUNT;
+const HASH_LENGTH: usize = FUZZY_HASH_LENGTH + 32;
+/// Length of a jarm hash decoded to bytes, two hexadecimal characters per byte
+pub const HASH_BYTES_LENGTH: usize = HASH_LENGTH / 2;
+/// Cipher index of a cipher suite missing from `JARM_CIPHERS`
+const UNKNOWN_CIPHER_INDEX: u8 = JARM_CIPHERS.len() as u8 + 1;
+
+/// What a probe got from the server, as written in a jarm hash by `cipher_bytes` and `version_byte`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProbeFingerprint {
+    /// 1-based position of the chosen cipher suite in the jarm cipher list, 0 without response
+    pub cipher_index: u8,
+    /// `a` for SSL 3.0 up to `f`, `0` without response
+    pub version: char,
+}
+
+impl ProbeFingerprint {
+    /// The server didn't answer this probe with a server hello
+    pub fn is_no_response(&self) -> bool {
+        self.cipher_index == 0
+    }
+
+    /// Code of the chosen cipher suite, e.g. `0xc02f`, unless there is no response or the cipher is not in the jarm list
+    pub fn cipher_suite(&self) -> Option<u16> {
+        let index = usize::from(self.cipher_index).checked_sub(1)?;
+        JARM_CIPHERS.get(index).map(|bytes| u16::from_be_bytes(**bytes))
+    }
+
+    /// Chosen protocol version, e.g. `0x0303` for TLS 1.2, unless there is no response
+    pub fn protocol_version(&self) -> Option<u16> {
+        match self.version {
+            'a'..='f' => Some(0x0300 + (self.version as u16 - 'a' as u16)),
+            _ => None,
+        }
+    }
+}
+
+/// A 62 characters jarm fingerprint: a cipher index and a version for each probe,
+/// followed by the truncated sha256 of the ALPNs and extensions
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct JarmHash {
+    hash: String,
+}
+
+impl JarmHash {
+    /// Hash of a server answering none of the probes, usually not speaking TLS at all
+    pub fn no_tls() -> JarmHash {
+        JarmHash { hash: "0".repeat(HASH_LENGTH) }
+    }
+
+    pub fn is_no_tls(&self) -> bool {
+        self.hash.bytes().all(|byte| byte == b'0')
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.hash
+    }
+
+    /// The hash decoded from hexadecimal, to store it compactly
+    pub fn to_bytes(&self) -> [u8; HASH_BYTES_LENGTH] {
+        let mut bytes = [0; HASH_BYTES_LENGTH];
+        hex::decode_to_slice(&self.hash, &mut bytes).expect("a parsed hash is hexadecimal");
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8; HASH_BYTES_LENGTH]) -> Result<JarmHash, JarmError> {
+        hex::encode(bytes).parse()
+    }
+
+    /// What the probe at `index`, from 0 to 9 in the order of the scan, got from the server
+    pub fn probe(&self, index: usize) -> Option<ProbeFingerprint> {
+        if index >= PROBE_COUNT {
+            return None;
+        }
+        let part = &self.hash[3 * index..3 * index + 3];
+        Some(ProbeFingerprint {
+            cipher_index: u8::from_str_radix(&part[..2], 16).ok()?,
+            version: part[2..].chars().next()?,
+        })
+    }
+
+    pub fn probes(&self) -> Vec<ProbeFingerprint> {
+        (0..PROBE_COUNT).filter_map(|index| self.probe(index)).collect()
+    }
+
+    /// First 32 hexadecimal characters of the sha256 of the ALPNs and extensions of every server hello
+    pub fn extensions_digest(&self) -> &str {
+        &self.hash[FUZZY_HASH_LENGTH..]
+    }
+}
+
+impl fmt::Display for JarmHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.hash)
+    }
+}
+
+/// Parse a jarm hash, case insensitive
+impl FromStr for JarmHash {
+    type Err = JarmError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason| JarmError::InvalidHash { hash: s.to_string(), reason };
+        if s.len() != HASH_LENGTH {
+            return Err(invalid("a jarm hash is 62 characters long"));
+        }
+        if !s.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+            return Err(invalid("a jarm hash is made of hexadecimal characters"));
+        }
+        let hash = JarmHash { hash: s.to_ascii_lowercase() };
+        let probes = hash.probes();
+        for probe in &probes {
+            if probe.cipher_index > UNKNOWN_CIPHER_INDEX {
+                return Err(invalid("cipher index out of the jarm cipher list"));
+            }
+            if probe.version != '0' && probe.protocol_version().is_none() {
+                return Err(invalid("version is not between 'a' and 'f'"));
+            }
+            if probe.is_no_response() != (probe.version == '0') {
+                return Err(invalid("cipher and version disagree on the probe having a response"));
+            }
+        }
+        if probes.iter().all(ProbeFingerprint::is_no_response) && !hash.is_no_tls() {
+            return Err(invalid("extensions digest without any response"));
+        }
+        Ok(hash)
+    }
+}
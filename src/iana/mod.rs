@@ -0,0 +1,216 @@
+use crate::hash::JarmHash;
+use crate::JarmPart;
+
+/// Names of the probes of a scan in the order of `Jarm::new`, as in the reference implementation
+pub const PROBE_NAMES: [&str; 10] = [
+    "tls1_2_forward", "tls1_2_reverse", "tls1_2_top_half", "tls1_2_bottom_half", "tls1_2_middle_out",
+    "tls1_1_middle_out", "tls1_3_forward", "tls1_3_reverse", "tls1_3_invalid", "tls1_3_middle_out",
+];
+
+/// IANA name of a cipher suite of the jarm cipher list
+pub fn cipher_suite_name(cipher: u16) -> Option<&'static str> {
+    let name = match cipher {
+        0x0004 => "TLS_RSA_WITH_RC4_128_MD5",
+        0x0005 => "TLS_RSA_WITH_RC4_128_SHA",
+        0x0007 => "TLS_RSA_WITH_IDEA_CBC_SHA",
+        0x000a => "TLS_RSA_WITH_3DES_EDE_CBC_SHA",
+        0x0016 => "TLS_DHE_RSA_WITH_3DES_EDE_CBC_SHA",
+        0x002f => "TLS_RSA_WITH_AES_128_CBC_SHA",
+        0x0033 => "TLS_DHE_RSA_WITH_AES_128_CBC_SHA",
+        0x0035 => "TLS_RSA_WITH_AES_256_CBC_SHA",
+        0x0039 => "TLS_DHE_RSA_WITH_AES_256_CBC_SHA",
+        0x003c => "TLS_RSA_WITH_AES_128_CBC_SHA256",
+        0x003d => "TLS_RSA_WITH_AES_256_CBC_SHA256",
+        0x0041 => "TLS_RSA_WITH_CAMELLIA_128_CBC_SHA",
+        0x0045 => "TLS_DHE_RSA_WITH_CAMELLIA_128_CBC_SHA",
+        0x0067 => "TLS_DHE_RSA_WITH_AES_128_CBC_SHA256",
+        0x006b => "TLS_DHE_RSA_WITH_AES_256_CBC_SHA256",
+        0x0084 => "TLS_RSA_WITH_CAMELLIA_256_CBC_SHA",
+        0x0088 => "TLS_DHE_RSA_WITH_CAMELLIA_256_CBC_SHA",
+        0x009a => "TLS_DHE_RSA_WITH_SEED_CBC_SHA",
+        0x009c => "TLS_RSA_WITH_AES_128_GCM_SHA256",
+        0x009d => "TLS_RSA_WITH_AES_256_GCM_SHA384",
+        0x009e => "TLS_DHE_RSA_WITH_AES_128_GCM_SHA256",
+        0x009f => "TLS_DHE_RSA_WITH_AES_256_GCM_SHA384",
+        0x00ba => "TLS_RSA_WITH_CAMELLIA_128_CBC_SHA256",
+        0x00be => "TLS_DHE_RSA_WITH_CAMELLIA_128_CBC_SHA256",
+        0x00c0 => "TLS_RSA_WITH_CAMELLIA_256_CBC_SHA256",
+        0x00c4 => "TLS_DHE_RSA_WITH_CAMELLIA_256_CBC_SHA256",
+        0xc007 => "TLS_ECDHE_ECDSA_WITH_RC4_128_SHA",
+        0xc008 => "TLS_ECDHE_ECDSA_WITH_3DES_EDE_CBC_SHA",
+        0xc009 => "TLS_ECDHE_ECDSA_WITH_AES_128_CBC_SHA",
+        0xc00a => "TLS_ECDHE_ECDSA_WITH_AES_256_CBC_SHA",
+        0xc011 => "TLS_ECDHE_RSA_WITH_RC4_128_SHA",
+        0xc012 => "TLS_ECDHE_RSA_WITH_3DES_EDE_CBC_SHA",
+        0xc013 => "TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA",
+        0xc014 => "TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA",
+        0xc023 => "TLS_ECDHE_ECDSA_WITH_AES_128_CBC_SHA256",
+        0xc024 => "TLS_ECDHE_ECDSA_WITH_AES_256_CBC_SHA384",
+        0xc027 => "TLS_ECDHE_RSA_WITH_AES_128_CBC_SHA256",
+        0xc028 => "TLS_ECDHE_RSA_WITH_AES_256_CBC_SHA384",
+        0xc02b => "TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256",
+        0xc02c => "TLS_ECDHE_ECDSA_WITH_AES_256_GCM_SHA384",
+        0xc02f => "TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256",
+        0xc030 => "TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384",
+        0xc060 => "TLS_ECDHE_RSA_WITH_ARIA_128_GCM_SHA256",
+        0xc061 => "TLS_ECDHE_RSA_WITH_ARIA_256_GCM_SHA384",
+        0xc072 => "TLS_ECDHE_ECDSA_WITH_CAMELLIA_128_CBC_SHA256",
+        0xc073 => "TLS_ECDHE_ECDSA_WITH_CAMELLIA_256_CBC_SHA384",
+        0xc076 => "TLS_ECDHE_RSA_WITH_CAMELLIA_128_CBC_SHA256",
+        0xc077 => "TLS_ECDHE_RSA_WITH_CAMELLIA_256_CBC_SHA384",
+        0xc09c => "TLS_RSA_WITH_AES_128_CCM",
+        0xc09d => "TLS_RSA_WITH_AES_256_CCM",
+        0xc09e => "TLS_DHE_RSA_WITH_AES_128_CCM",
+        0xc09f => "TLS_DHE_RSA_WITH_AES_256_CCM",
+        0xc0a0 => "TLS_RSA_WITH_AES_128_CCM_8",
+        0xc0a1 => "TLS_RSA_WITH_AES_256_CCM_8",
+        0xc0a2 => "TLS_DHE_RSA_WITH_AES_128_CCM_8",
+        0xc0a3 => "TLS_DHE_RSA_WITH_AES_256_CCM_8",
+        0xc0ac => "TLS_ECDHE_ECDSA_WITH_AES_128_CCM",
+        0xc0ad => "TLS_ECDHE_ECDSA_WITH_AES_256_CCM",
+        0xc0ae => "TLS_ECDHE_ECDSA_WITH_AES_128_CCM_8",
+        0xc0af => "TLS_ECDHE_ECDSA_WITH_AES_256_CCM_8",
+        // Codes of the draft ChaCha20 suites, never registered, named as by OpenSSL and Wireshark
+        0xcc13 => "TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256_OLD",
+        0xcc14 => "TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256_OLD",
+        0xcca8 => "TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256",
+        0xcca9 => "TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256",
+        0x1301 => "TLS_AES_128_GCM_SHA256",
+        0x1302 => "TLS_AES_256_GCM_SHA384",
+        0x1303 => "TLS_CHACHA20_POLY1305_SHA256",
+        0x1304 => "TLS_AES_128_CCM_SHA256",
+        0x1305 => "TLS_AES_128_CCM_8_SHA256",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Name of a protocol version of a server hello, e.g. `TLS 1.2` for `0x0303`
+pub fn version_name(version: u16) -> Option<&'static str> {
+    let name = match version {
+        0x0300 => "SSL 3.0",
+        0x0301 => "TLS 1.0",
+        0x0302 => "TLS 1.1",
+        0x0303 => "TLS 1.2",
+        0x0304 => "TLS 1.3",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// IANA name of an extension type, GREASE values included
+pub fn extension_name(extension: u16) -> Option<&'static str> {
+    let name = match extension {
+        0 => "server_name",
+        1 => "max_fragment_length",
+        2 => "client_certificate_url",
+        3 => "trusted_ca_keys",
+        4 => "truncated_hmac",
+        5 => "status_request",
+        6 => "user_mapping",
+        7 => "client_authz",
+        8 => "server_authz",
+        9 => "cert_type",
+        10 => "supported_groups",
+        11 => "ec_point_formats",
+        12 => "srp",
+        13 => "signature_algorithms",
+        14 => "use_srtp",
+        15 => "heartbeat",
+        16 => "application_layer_protocol_negotiation",
+        17 => "status_request_v2",
+        18 => "signed_certificate_timestamp",
+        19 => "client_certificate_type",
+        20 => "server_certificate_type",
+        21 => "padding",
+        22 => "encrypt_then_mac",
+        23 => "extended_master_secret",
+        24 => "token_binding",
+        25 => "cached_info",
+        26 => "tls_lts",
+        27 => "compress_certificate",
+        28 => "record_size_limit",
+        29 => "pwd_protect",
+        30 => "pwd_clear",
+        31 => "password_salt",
+        32 => "ticket_pinning",
+        33 => "tls_cert_with_extern_psk",
+        34 => "delegated_credential",
+        35 => "session_ticket",
+        36 => "TLMSP",
+        37 => "TLMSP_proxying",
+        38 => "TLMSP_delegate",
+        39 => "supported_ekt_ciphers",
+        41 => "pre_shared_key",
+        42 => "early_data",
+        43 => "supported_versions",
+        44 => "cookie",
+        45 => "psk_key_exchange_modes",
+        47 => "certificate_authorities",
+        48 => "oid_filters",
+        49 => "post_handshake_auth",
+        50 => "signature_algorithms_cert",
+        51 => "key_share",
+        52 => "transparency_info",
+        53 => "connection_id_deprecated",
+        54 => "connection_id",
+        55 => "external_id_hash",
+        56 => "external_session_id",
+        57 => "quic_transport_parameters",
+        58 => "ticket_request",
+        59 => "dnssec_chain",
+        60 => "sequence_number_encryption_algorithms",
+        61 => "rrc",
+        // Not registered, but sent by widespread servers
+        0x3374 => "next_protocol_negotiation",
+        0x4469 => "application_settings",
+        0xfe0d => "encrypted_client_hello",
+        0xff01 => "renegotiation_info",
+        _ if is_grease(extension) => "GREASE",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// GREASE values of RFC 8701: `0x0a0a`, `0x1a1a` up to `0xfafa`
+pub(crate) fn is_grease(value: u16) -> bool {
+    value & 0x0f0f == 0x0a0a && value >> 8 == value & 0xff
+}
+
+/// One line per probe naming the cipher suite and version the server chose
+pub fn explain_hash(hash: &JarmHash) -> String {
+    let mut lines: Vec<String> = PROBE_NAMES.iter().zip(hash.probes()).map(|(name, probe)| {
+        if probe.is_no_response() {
+            return format!("{name}: no response");
+        }
+        let cipher = match probe.cipher_suite() {
+            Some(cipher) => describe(cipher, cipher_suite_name(cipher)),
+            None => "cipher suite missing from the jarm list".to_string(),
+        };
+        let version = probe.protocol_version().unwrap_or_default();
+        format!("{name}: {cipher}, {}", describe(version, version_name(version)))
+    }).collect();
+    lines.push(format!("ALPN and extensions digest: {}", hash.extensions_digest()));
+    lines.join("\n")
+}
+
+/// One line per probe naming the cipher suite, version, ALPN and extensions of the server hello
+pub fn explain_parts(parts: &[JarmPart]) -> String {
+    let lines: Vec<String> = PROBE_NAMES.iter().zip(parts).map(|(name, part)| {
+        let JarmPart::ServerHello { cipher, version, alpn, extensions } = part else {
+            return format!("{name}: no response");
+        };
+        let alpn = alpn.as_ref().map(|alpn| format!(", ALPN {alpn}")).unwrap_or_default();
+        let extensions: Vec<String> = extensions.iter().map(|e| describe(*e, extension_name(*e))).collect();
+        format!(
+            "{name}: {}, {}{alpn}, extensions [{}]",
+            describe(*cipher, cipher_suite_name(*cipher)), describe(*version, version_name(*version)), extensions.join(", "),
+        )
+    }).collect();
+    lines.join("\n")
+}
+
+/// `name (0x1301)`, or `unknown (0x1234)` without a name
+fn describe(code: u16, name: Option<&str>) -> String {
+    format!("{} (0x{code:04x})", name.unwrap_or("unknown"))
+}
@@ -0,0 +1,225 @@
+use crate::error::JarmError;
+use super::scan_output_error;
+
+/// Deepest nesting of arrays and objects accepted, as in serde_json. It bounds the recursion of the parser.
+const MAX_DEPTH: usize = 128;
+
+/// JSON value, numbers are kept as `f64`
+pub(super) enum Value {
+    /// `true`, `false` or `null`, their value is not needed
+    Literal,
+    Number(f64),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    pub(super) fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(members) => members.iter().find(|(name, _)| name == key).map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub(super) fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub(super) fn as_array(&self) -> Option<&[Value]> {
+        match self {
+            Value::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    pub(super) fn as_u16(&self) -> Option<u16> {
+        match self {
+            Value::Number(number) if number.fract() == 0.0 && (0.0..=u16::MAX as f64).contains(number) => Some(*number as u16),
+            Value::String(number) => number.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+/// Parse a JSON document. Trailing commas, as written by some masscan versions, are accepted.
+pub(super) fn parse(json: &str) -> Result<Value, JarmError> {
+    let mut parser = Parser { json: json.as_bytes(), offset: 0, depth: 0 };
+    let value = parser.value()?;
+    parser.skip_whitespaces();
+    match parser.offset == parser.json.len() {
+        true => Ok(value),
+        false => Err(parser.error("unexpected data after the JSON document")),
+    }
+}
+
+struct Parser<'a> {
+    json: &'a [u8],
+    offset: usize,
+    /// Arrays and objects containing the current value
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, reason: &'static str) -> JarmError {
+        scan_output_error(self.offset, reason)
+    }
+
+    fn skip_whitespaces(&mut self) {
+        while self.json.get(self.offset).is_some_and(u8::is_ascii_whitespace) {
+            self.offset += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespaces();
+        self.json.get(self.offset).copied()
+    }
+
+    fn expect(&mut self, literal: &[u8]) -> Result<Value, JarmError> {
+        match self.json[self.offset..].starts_with(literal) {
+            true => {
+                self.offset += literal.len();
+                Ok(Value::Literal)
+            },
+            false => Err(self.error("invalid JSON literal")),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, JarmError> {
+        match self.peek() {
+            Some(b'{' | b'[') if self.depth >= MAX_DEPTH => Err(self.error("JSON nested too deeply")),
+            Some(b'{') => self.nested(Self::object),
+            Some(b'[') => self.nested(Self::array),
+            Some(b'"') => Ok(Value::String(self.string()?)),
+            Some(b't') => self.expect(b"true"),
+            Some(b'f') => self.expect(b"false"),
+            Some(b'n') => self.expect(b"null"),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => Err(self.error("unexpected character in JSON")),
+            None => Err(self.error("unexpected end of JSON")),
+        }
+    }
+
+    fn nested(&mut self, parse: fn(&mut Self) -> Result<Value, JarmError>) -> Result<Value, JarmError> {
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn object(&mut self) -> Result<Value, JarmError> {
+        self.offset += 1;  // {
+        let mut members = Vec::new();
+        loop {
+            match self.peek() {
+                Some(b'}') => {
+                    self.offset += 1;
+                    return Ok(Value::Object(members));
+                },
+                Some(b'"') => {},
+                _ => return Err(self.error("expected a JSON object key")),
+            }
+            let key = self.string()?;
+            if self.peek() != Some(b':') {
+                return Err(self.error("expected ':' after a JSON object key"));
+            }
+            self.offset += 1;
+            members.push((key, self.value()?));
+            match self.peek() {
+                Some(b',') => self.offset += 1,
+                Some(b'}') => {},
+                _ => return Err(self.error("expected ',' or '}' in a JSON object")),
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Value, JarmError> {
+        self.offset += 1;  // [
+        let mut values = Vec::new();
+        loop {
+            if self.peek() == Some(b']') {
+                self.offset += 1;
+                return Ok(Value::Array(values));
+            }
+            values.push(self.value()?);
+            match self.peek() {
+                Some(b',') => self.offset += 1,
+                Some(b']') => {},
+                _ => return Err(self.error("expected ',' or ']' in a JSON array")),
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, JarmError> {
+        self.offset += 1;  // opening quote
+        let mut bytes = Vec::new();
+        loop {
+            let Some(&byte) = self.json.get(self.offset) else {
+                return Err(self.error("unterminated JSON string"));
+            };
+            self.offset += 1;
+            match byte {
+                b'"' => return String::from_utf8(bytes).map_err(|_| self.error("invalid UTF-8 in JSON string")),
+                b'\\' => {
+                    let escaped = match self.json.get(self.offset) {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => self.unicode_escape()?,
+                        _ => return Err(self.error("invalid escape in JSON string")),
+                    };
+                    self.offset += 1;
+                    bytes.extend(escaped.encode_utf8(&mut [0; 4]).as_bytes());
+                },
+                _ => bytes.push(byte),
+            }
+        }
+    }
+
+    /// Character of a `\uXXXX` escape, a surrogate pair spanning two escapes.
+    /// The offset is left on the last hexadecimal digit.
+    fn unicode_escape(&mut self) -> Result<char, JarmError> {
+        let high = self.hex_code_unit()?;
+        if !(0xd800..0xdc00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| self.error("invalid unicode escape in JSON string"));
+        }
+        if !self.json[self.offset + 1..].starts_with(b"\\u") {
+            return Err(self.error("unpaired surrogate in JSON string"));
+        }
+        self.offset += 2;
+        let low = self.hex_code_unit()?;
+        if !(0xdc00..0xe000).contains(&low) {
+            return Err(self.error("unpaired surrogate in JSON string"));
+        }
+        char::from_u32(0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00)).ok_or_else(|| self.error("invalid unicode escape in JSON string"))
+    }
+
+    fn hex_code_unit(&mut self) -> Result<u32, JarmError> {
+        let digits = self.json.get(self.offset + 1..self.offset + 5).ok_or_else(|| self.error("truncated unicode escape in JSON string"))?;
+        let code_unit = std::str::from_utf8(digits).ok()
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .ok_or_else(|| self.error("invalid unicode escape in JSON string"))?;
+        self.offset += 4;
+        Ok(code_unit)
+    }
+
+    fn number(&mut self) -> Result<Value, JarmError> {
+        let start = self.offset;
+        while self.json.get(self.offset).is_some_and(|byte| matches!(byte, b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')) {
+            self.offset += 1;
+        }
+        std::str::from_utf8(&self.json[start..self.offset]).ok()
+            .and_then(|number| number.parse().ok())
+            .map(Value::Number)
+            .ok_or_else(|| scan_output_error(start, "invalid JSON number"))
+    }
+}
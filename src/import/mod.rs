@@ -0,0 +1,154 @@
+mod json;
+mod xml;
+
+use std::collections::HashSet;
+use std::net::{IpAddr, SocketAddr};
+use std::vec;
+use crate::error::JarmError;
+use crate::target::Target;
+use self::xml::Tag;
+
+/// Ports of an nmap output to import
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ServiceFilter {
+    /// Every open TCP port
+    #[default]
+    Any,
+    /// Only the open TCP ports nmap identified as TLS, e.g. `https` or a service tunnelled in SSL
+    Tls,
+}
+
+/// Targets of a scan output, in the order they appear and without duplicates
+#[derive(Debug, Default)]
+pub struct ImportedTargets {
+    pub targets: Vec<Target>,
+    /// Error of each entry left out, e.g. an invalid host name or port
+    pub skipped: Vec<JarmError>,
+    seen: HashSet<Target>,
+}
+
+impl ImportedTargets {
+    fn push(&mut self, target: Result<Target, JarmError>) {
+        match target {
+            Ok(target) => if self.seen.insert(target.clone()) {
+                self.targets.push(target);
+            },
+            Err(e) => self.skipped.push(e),
+        }
+    }
+}
+
+impl IntoIterator for ImportedTargets {
+    type Item = Target;
+    type IntoIter = vec::IntoIter<Target>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.targets.into_iter()
+    }
+}
+
+/// Open port of an nmap host, while reading it
+struct NmapPort {
+    port: u16,
+    tcp: bool,
+    open: bool,
+    tls: bool,
+}
+
+/// Targets of the open TCP ports of an nmap XML output (`-oX`).
+/// A host scanned by name keeps it for the SNI, the probes being sent to the address nmap scanned.
+/// Only a malformed document fails the import, an invalid host is skipped.
+pub fn from_nmap_xml(xml: &str, filter: ServiceFilter) -> Result<ImportedTargets, JarmError> {
+    let mut targets = ImportedTargets::default();
+    let mut ip: Option<IpAddr> = None;
+    let mut host_name: Option<String> = None;
+    let mut ports: Vec<u16> = Vec::new();
+    let mut port: Option<NmapPort> = None;
+
+    for tag in xml::parse_tags(xml)? {
+        match &tag {
+            Tag::Open { name: "host", .. } => {
+                (ip, host_name) = (None, None);
+                ports.clear();
+            },
+            Tag::Open { name: "address", .. } if matches!(tag.attribute("addrtype"), Some("ipv4" | "ipv6")) => {
+                ip = ip.or_else(|| tag.attribute("addr").and_then(|addr| addr.parse().ok()));
+            },
+            Tag::Open { name: "hostname", .. } if tag.attribute("type") == Some("user") => {
+                host_name = tag.attribute("name").map(str::to_string);
+            },
+            Tag::Open { name: "port", .. } => {
+                port = tag.attribute("portid").and_then(|id| id.parse().ok()).map(|id| NmapPort {
+                    port: id,
+                    tcp: tag.attribute("protocol") == Some("tcp"),
+                    open: false,
+                    tls: false,
+                });
+            },
+            Tag::Open { name: "state", .. } => if let Some(port) = port.as_mut() {
+                port.open = tag.attribute("state") == Some("open");
+            },
+            Tag::Open { name: "service", .. } => if let Some(port) = port.as_mut() {
+                let name = tag.attribute("name").unwrap_or_default();
+                port.tls = tag.attribute("tunnel") == Some("ssl") || name == "https" || name.starts_with("ssl");
+            },
+            Tag::Close { name: "port" } => {
+                if let Some(port) = port.take().filter(|port| port.tcp && port.open && (filter == ServiceFilter::Any || port.tls)) {
+                    ports.push(port.port);
+                }
+            },
+            Tag::Close { name: "host" } => if let Some(ip) = ip {
+                for &port in &ports {
+                    targets.push(match &host_name {
+                        Some(name) => Target::new(name, port).map(|target| target.with_address(SocketAddr::new(ip, port))),
+                        None => Target::new(&ip.to_string(), port),
+                    });
+                }
+            },
+            _ => {},
+        }
+    }
+    Ok(targets)
+}
+
+/// Targets of the open TCP ports of a masscan JSON output (`-oJ`), see `from_nmap_xml` for the errors
+pub fn from_masscan_json(json: &str) -> Result<ImportedTargets, JarmError> {
+    let document = json::parse(json)?;
+    let hosts = document.as_array().ok_or_else(|| scan_output_error(0, "masscan output is not a JSON array"))?;
+    let mut targets = ImportedTargets::default();
+    for host in hosts {
+        let (Some(ip), Some(ports)) = (host.get("ip").and_then(|ip| ip.as_str()), host.get("ports").and_then(|ports| ports.as_array())) else {
+            continue;  // e.g. the `finished` record of old versions
+        };
+        for port in ports {
+            let open_tcp = port.get("proto").and_then(|proto| proto.as_str()) == Some("tcp")
+                && port.get("status").and_then(|status| status.as_str()) == Some("open");
+            if let (true, Some(port)) = (open_tcp, port.get("port").and_then(|port| port.as_u16())) {
+                targets.push(Target::new(ip, port));
+            }
+        }
+    }
+    Ok(targets)
+}
+
+/// Targets of the open TCP ports of a masscan list output (`-oL`), made of lines such as
+/// `open tcp 443 192.0.2.1 1600000000`. A line with an invalid port or address is skipped.
+pub fn from_masscan_list(list: &str) -> ImportedTargets {
+    let mut targets = ImportedTargets::default();
+    let mut offset = 0;
+    for line in list.split_inclusive('\n') {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if let ["open", "tcp", port, ip, ..] = fields.as_slice() {
+            targets.push(match port.parse() {
+                Ok(port) => Target::new(ip, port),
+                Err(_) => Err(scan_output_error(offset, "invalid port in masscan output")),
+            });
+        }
+        offset += line.len();
+    }
+    targets
+}
+
+pub(crate) fn scan_output_error(offset: usize, reason: &'static str) -> JarmError {
+    JarmError::ScanOutput { offset, reason }
+}
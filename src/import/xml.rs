@@ -0,0 +1,100 @@
+use crate::error::JarmError;
+use super::scan_output_error;
+
+/// Tag of an XML document, the text between tags is not needed
+pub(super) enum Tag<'a> {
+    Open { name: &'a str, attributes: Vec<(&'a str, String)> },
+    Close { name: &'a str },
+}
+
+impl Tag<'_> {
+    pub(super) fn attribute(&self, name: &str) -> Option<&str> {
+        match self {
+            Tag::Open { attributes, .. } => attributes.iter()
+                .find(|(attribute, _)| *attribute == name)
+                .map(|(_, value)| value.as_str()),
+            Tag::Close { .. } => None,
+        }
+    }
+}
+
+/// Every tag of the document in order, a self closing tag being followed by its closing tag.
+/// Comments, processing instructions and declarations are skipped.
+pub(super) fn parse_tags(xml: &str) -> Result<Vec<Tag<'_>>, JarmError> {
+    let mut tags = Vec::new();
+    let mut offset = 0;
+    while let Some(start) = xml[offset..].find('<').map(|start| offset + start) {
+        let rest = &xml[start..];
+        let skipped_until = if rest.starts_with("<!--") {
+            Some("-->")
+        } else if rest.starts_with("<?") || rest.starts_with("<!") {
+            Some(">")
+        } else {
+            None
+        };
+        if let Some(terminator) = skipped_until {
+            let end = rest.find(terminator).ok_or_else(|| scan_output_error(start, "unterminated XML declaration or comment"))?;
+            offset = start + end + terminator.len();
+            continue;
+        }
+
+        let end = tag_end(rest).ok_or_else(|| scan_output_error(start, "unterminated XML tag"))?;
+        let content = &rest[1..end];
+        match content.strip_prefix('/') {
+            Some(name) => tags.push(Tag::Close { name: name.trim() }),
+            None => {
+                let self_closing = content.ends_with('/');
+                let content = content.strip_suffix('/').unwrap_or(content);
+                let (name, attributes) = content.split_once(char::is_whitespace).unwrap_or((content, ""));
+                let attributes = parse_attributes(attributes).ok_or_else(|| scan_output_error(start, "invalid XML attribute"))?;
+                tags.push(Tag::Open { name, attributes });
+                if self_closing {
+                    tags.push(Tag::Close { name });
+                }
+            },
+        }
+        offset = start + end + 1;
+    }
+    Ok(tags)
+}
+
+/// Position of the `>` ending the tag starting `rest`, ignoring the ones in attribute values
+fn tag_end(rest: &str) -> Option<usize> {
+    let mut quote = None;
+    for (index, c) in rest.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), _) if q == c => quote = None,
+            (None, '>') => return Some(index),
+            _ => {},
+        }
+    }
+    None
+}
+
+fn parse_attributes(mut attributes: &str) -> Option<Vec<(&str, String)>> {
+    let mut parsed = Vec::new();
+    loop {
+        attributes = attributes.trim_start();
+        if attributes.is_empty() {
+            return Some(parsed);
+        }
+        let (name, rest) = attributes.split_once('=')?;
+        let rest = rest.trim_start();
+        let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let (value, rest) = rest[1..].split_once(quote)?;
+        parsed.push((name.trim(), decode_entities(value)));
+        attributes = rest;
+    }
+}
+
+fn decode_entities(value: &str) -> String {
+    if !value.contains('&') {
+        return value.to_string();
+    }
+    value.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
@@ -0,0 +1,185 @@
+//! In-memory index of jarm hashes answering "which hashes differ from this one on at most N probes".
+//!
+//! Hashes are kept in a BK-tree over the number of differing probes, a metric taking only eleven values,
+//! so a radius search only visits the subtrees whose distance to their parent is within the radius.
+
+use crate::error::JarmError;
+use crate::hash::{JarmHash, HASH_BYTES_LENGTH, PROBE_COUNT};
+
+/// Start of a serialized index, the last byte being the format version
+const MAGIC: &[u8; 8] = b"JARMIDX\x01";
+const HEADER_LENGTH: usize = MAGIC.len() + 8;
+
+/// Hash of the index and its children by distance, 0 meaning no child as the root is never a child
+struct Node {
+    hash: [u8; HASH_BYTES_LENGTH],
+    probes: Probes,
+    children: [u32; PROBE_COUNT + 1],
+}
+
+/// Cipher and version of each probe as the 12 bits of their three hexadecimal digits, compared faster than the hash
+type Probes = [u16; PROBE_COUNT];
+
+/// Hash found by a radius search
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Neighbour {
+    pub hash: JarmHash,
+    /// Number of probes with a different cipher or version
+    pub distance: usize,
+}
+
+/// Set of up to `u32::MAX` jarm hashes searchable by the number of differing probes
+#[derive(Default)]
+pub struct HashIndex {
+    nodes: Vec<Node>,
+}
+
+impl HashIndex {
+    pub fn new() -> HashIndex {
+        HashIndex::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Add the hash to the index, false if it was already there
+    pub fn insert(&mut self, hash: &JarmHash) -> bool {
+        self.insert_bytes(hash.to_bytes())
+    }
+
+    pub fn contains(&self, hash: &JarmHash) -> bool {
+        let hash = hash.to_bytes();
+        let probes = probes(&hash);
+        let mut current = 0;
+        while let Some(node) = self.nodes.get(current) {
+            if node.hash == hash {
+                return true;
+            }
+            current = match node.children[probe_distance(&node.probes, &probes)] {
+                0 => return false,
+                child => child as usize,
+            };
+        }
+        false
+    }
+
+    /// Every hash differing from `hash` on at most `radius` probes, the closest first.
+    /// The extensions digest is not compared, the hash itself is returned if indexed.
+    pub fn within(&self, hash: &JarmHash, radius: usize) -> Vec<Neighbour> {
+        let query = probes(&hash.to_bytes());
+        let mut found = Vec::new();
+        let mut pending = match self.nodes.is_empty() {
+            true => vec![],
+            false => vec![0],
+        };
+        while let Some(current) = pending.pop() {
+            let node = &self.nodes[current];
+            let distance = probe_distance(&node.probes, &query);
+            if distance <= radius {
+                found.push((distance, node.hash));
+            }
+            // Triangle inequality: hashes under a child at another distance are too far
+            let closest = distance.saturating_sub(radius);
+            let farthest = (distance + radius).min(PROBE_COUNT);
+            pending.extend(node.children[closest..=farthest].iter().filter(|child| **child != 0).map(|child| *child as usize));
+        }
+        found.sort();
+        found.into_iter()
+            .map(|(distance, hash)| Neighbour { hash: JarmHash::from_bytes(&hash).expect("indexed hashes are valid"), distance })
+            .collect()
+    }
+
+    /// Compact serialization: a header with the number of hashes, then each hash decoded to 31 bytes
+    /// in insertion order, so the same tree is built back by `from_bytes`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LENGTH + self.nodes.len() * HASH_BYTES_LENGTH);
+        bytes.extend(MAGIC);
+        bytes.extend((self.nodes.len() as u64).to_le_bytes());
+        for node in &self.nodes {
+            bytes.extend(node.hash);
+        }
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<HashIndex, JarmError> {
+        if bytes.get(..MAGIC.len()) != Some(MAGIC) {
+            return Err(index_error(0, "not a hash index or unsupported version"));
+        }
+        let count = bytes.get(MAGIC.len()..HEADER_LENGTH)
+            .map(|count| u64::from_le_bytes(count.try_into().unwrap()))
+            .ok_or_else(|| index_error(MAGIC.len(), "truncated header"))?;
+        let hashes = &bytes[HEADER_LENGTH..];
+        if count.checked_mul(HASH_BYTES_LENGTH as u64) != Some(hashes.len() as u64) {
+            return Err(index_error(HEADER_LENGTH, "the number of hashes doesn't match the header"));
+        }
+
+        let mut index = HashIndex { nodes: Vec::with_capacity(count as usize) };
+        for (position, hash) in hashes.chunks_exact(HASH_BYTES_LENGTH).enumerate() {
+            let hash: [u8; HASH_BYTES_LENGTH] = hash.try_into().unwrap();
+            let offset = HEADER_LENGTH + position * HASH_BYTES_LENGTH;
+            JarmHash::from_bytes(&hash).map_err(|_| index_error(offset, "invalid jarm hash"))?;
+            if !index.insert_bytes(hash) {
+                return Err(index_error(offset, "duplicated jarm hash"));
+            }
+        }
+        Ok(index)
+    }
+
+    fn insert_bytes(&mut self, hash: [u8; HASH_BYTES_LENGTH]) -> bool {
+        let new = self.nodes.len() as u32;
+        let probes = probes(&hash);
+        let mut current = 0;
+        while let Some(node) = self.nodes.get_mut(current) {
+            if node.hash == hash {
+                return false;
+            }
+            let child = &mut node.children[probe_distance(&node.probes, &probes)];
+            if *child == 0 {
+                *child = new;
+                break;
+            }
+            current = *child as usize;
+        }
+        self.nodes.push(Node { hash, probes, children: [0; PROBE_COUNT + 1] });
+        true
+    }
+}
+
+impl FromIterator<JarmHash> for HashIndex {
+    fn from_iter<T: IntoIterator<Item = JarmHash>>(hashes: T) -> Self {
+        let mut index = HashIndex::new();
+        for hash in hashes {
+            index.insert(&hash);
+        }
+        index
+    }
+}
+
+/// Number of probes on which the two hashes differ, by cipher or version
+pub fn distance(a: &JarmHash, b: &JarmHash) -> usize {
+    probe_distance(&probes(&a.to_bytes()), &probes(&b.to_bytes()))
+}
+
+fn probes(hash: &[u8; HASH_BYTES_LENGTH]) -> Probes {
+    // Two probes take three bytes
+    let mut probes = [0; PROBE_COUNT];
+    for (pair, bytes) in probes.chunks_exact_mut(2).zip(hash.chunks_exact(3)) {
+        let [first, middle, last] = [bytes[0], bytes[1], bytes[2]].map(u16::from);
+        pair[0] = first << 4 | middle >> 4;
+        pair[1] = (middle & 0x0f) << 8 | last;
+    }
+    probes
+}
+
+fn probe_distance(a: &Probes, b: &Probes) -> usize {
+    a.iter().zip(b).filter(|(a, b)| a != b).count()
+}
+
+fn index_error(offset: usize, reason: &'static str) -> JarmError {
+    JarmError::HashIndex { offset, reason }
+}
@@ -1,37 +1,156 @@
 pub mod error;
+pub mod alert;
+pub mod scanner;
+pub mod pcap;
+pub mod report;
+pub mod addresses;
+pub mod connect;
+pub mod resolver;
+pub mod transport;
+pub mod proxy;
+pub mod sni;
+pub mod vhosts;
+pub mod target;
+pub mod expand;
+pub mod import;
+pub mod hash;
+pub mod iana;
+pub mod similarity;
+pub mod index;
+mod response;
+#[cfg(feature = "tokio")]
+pub mod async_jarm;
 
 use rand::RngExt;
+use std::fmt;
 use std::str::FromStr;
 use sha2::{Sha256, Digest};
-use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
-use std::io::{Write, Read};
-use std::time::Duration;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::io::{self, ErrorKind, Write};
+use std::mem;
+use std::time::{Duration, Instant};
 use rand::seq::IndexedRandom;
+use crate::alert::{read_alert, TlsAlert};
 use crate::error::{DetailedError, JarmError};
+use crate::connect::{connect_first, sort_addresses, AddressPreference, Connection};
+use crate::report::{JarmReport, ProbeOutcome, ProbeReport};
+use crate::resolver::{dns_error, JarmResolver, SystemResolver};
+use crate::response::read_response;
+use crate::proxy::Proxy;
+use crate::sni::{domain_to_ascii, ServerName};
+use crate::transport::{JarmStream, JarmTransport, TcpTransport};
 
 const ALPN_EXTENSION: &[u8; 2] = b"\x00\x10";
 const SOCKET_BUFFER: u64 = 1484;
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+
+/// Result of a single probe.
+/// It is displayed and parsed in the raw format of the original implementation: `cipher|version|alpn|extensions`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JarmPart {
+    /// The server did not answer the probe with a server hello, `|||` in the raw format
+    NoResponse,
+    ServerHello {
+        /// Cipher suite selected by the server
+        cipher: u16,
+        /// Protocol version of the server hello
+        version: u16,
+        /// Protocol selected by the server through the ALPN extension
+        alpn: Option<String>,
+        /// Types of the extensions sent by the server, in order
+        extensions: Vec<u16>,
+    },
+}
 
-
-pub struct JarmPart {
-    pub raw: String
+impl fmt::Display for JarmPart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JarmPart::NoResponse => write!(f, "|||"),
+            JarmPart::ServerHello { cipher, version, alpn, extensions } => {
+                write!(f, "{cipher:04x}|{version:04x}|{}|{}", alpn.as_deref().unwrap_or_default(), format_extensions(extensions))
+            }
+        }
+    }
 }
 
-impl JarmPart {
-    pub fn new(raw: &str) -> JarmPart {
-        JarmPart {
-            raw: raw.to_string(),
+impl FromStr for JarmPart {
+    type Err = JarmError;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        if raw == "|||" {
+            return Ok(JarmPart::NoResponse);
         }
+        let malformed = |reason: &str| JarmError::MalformedPart(DetailedError::from(Box::from(format!("{reason} in {raw:?}"))));
+
+        let mut components = raw.splitn(3, '|');
+        let (Some(cipher), Some(version), Some(rest)) = (components.next(), components.next(), components.next()) else {
+            return Err(malformed("missing components"));
+        };
+        // The ALPN is the only component which may contain a '|'
+        let Some((alpn, extensions)) = rest.rsplit_once('|') else {
+            return Err(malformed("missing components"));
+        };
+        let parse_hex = |value: &str| match value.len() {
+            4 => u16::from_str_radix(value, 16).map_err(|_| malformed("invalid hexadecimal value")),
+            _ => Err(malformed("invalid hexadecimal value")),
+        };
+
+        Ok(JarmPart::ServerHello {
+            cipher: parse_hex(cipher)?,
+            version: parse_hex(version)?,
+            alpn: (!alpn.is_empty()).then(|| alpn.to_string()),
+            extensions: match extensions {
+                "" => Vec::new(),
+                _ => extensions.split('-').map(parse_hex).collect::<Result<_, _>>()?,
+            },
+        })
     }
 }
 
+/// Result of a single probe, the part used in the hash and the alert sent by the server if any
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProbeResult {
+    pub part: JarmPart,
+    pub alert: Option<TlsAlert>,
+}
+
+/// What to do when a probe fails, e.g. the connection is reset or times out
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FailurePolicy {
+    /// The scan stops at the first failed probe and returns its error
+    #[default]
+    Strict,
+    /// As in salesforce/jarm, a failed probe counts as no response (`|||`) and the scan goes on
+    Reference,
+}
+
 #[non_exhaustive]
 pub struct Jarm {
     pub parts: Vec<JarmPart>,
     pub queue: Vec<PacketSpecification>,
     pub rng: Box<dyn JarmRng + 'static>,
     pub timeout: Duration,
+    /// Maximum duration of a whole scan, across the connections of all the probes.
+    /// Each connection still times out after `timeout`, the probes not sent in time fail with a timeout.
+    pub scan_timeout: Option<Duration>,
+    pub failure_policy: FailurePolicy,
+    /// Address every probe is sent to, the host of the queue is then only used for the SNI.
+    /// When `None`, the host is resolved once per scan and the first address accepting a connection is used for all probes.
+    pub address: Option<SocketAddr>,
+    /// Address family tried first when the host resolves to several addresses
+    pub address_preference: AddressPreference,
+    /// Delay before trying the next resolved address while a connection attempt is pending
+    pub attempt_delay: Duration,
+    /// Resolver of the host of the queue, the system one by default
+    pub resolver: Arc<dyn JarmResolver>,
+    /// How the connection of each probe is opened, plain TCP by default
+    pub transport: Arc<dyn JarmTransport>,
+    /// Proxy every probe connection goes through. The host is then resolved by the proxy,
+    /// and the address of the proxy is the one reported.
+    pub proxy: Option<Proxy>,
 }
 
 impl Default for Jarm {
@@ -49,6 +168,7 @@ impl Jarm {
                 PacketSpecification {
                     host: host.clone(),
                     port: port.clone(),
+                    server_name: ServerName::Host,
                     tls_version: TlsVersion::TLS1_2,
                     cipher_list: CipherList::ALL,
                     cipher_order: CipherOrder::FORWARD,
@@ -60,6 +180,7 @@ impl Jarm {
                 PacketSpecification {
                     host: host.clone(),
                     port: port.clone(),
+                    server_name: ServerName::Host,
                     tls_version: TlsVersion::TLS1_2,
                     cipher_list: CipherList::ALL,
                     cipher_order: CipherOrder::REVERSE,
@@ -71,6 +192,7 @@ impl Jarm {
                 PacketSpecification {
                     host: host.clone(),
                     port: port.clone(),
+                    server_name: ServerName::Host,
                     tls_version: TlsVersion::TLS1_2,
                     cipher_list: CipherList::ALL,
                     cipher_order: CipherOrder::TOP_HALF,
@@ -82,6 +204,7 @@ impl Jarm {
                 PacketSpecification {
                     host: host.clone(),
                     port: port.clone(),
+                    server_name: ServerName::Host,
                     tls_version: TlsVersion::TLS1_2,
                     cipher_list: CipherList::ALL,
                     cipher_order: CipherOrder::BOTTOM_HALF,
@@ -93,6 +216,7 @@ impl Jarm {
                 PacketSpecification {
                     host: host.clone(),
                     port: port.clone(),
+                    server_name: ServerName::Host,
                     tls_version: TlsVersion::TLS1_2,
                     cipher_list: CipherList::ALL,
                     cipher_order: CipherOrder::MIDDLE_OUT,
@@ -104,6 +228,7 @@ impl Jarm {
                 PacketSpecification {
                     host: host.clone(),
                     port: port.clone(),
+                    server_name: ServerName::Host,
                     tls_version: TlsVersion::TLS1_1,
                     cipher_list: CipherList::ALL,
                     cipher_order: CipherOrder::FORWARD,
@@ -115,6 +240,7 @@ impl Jarm {
                 PacketSpecification {
                     host: host.clone(),
                     port: port.clone(),
+                    server_name: ServerName::Host,
                     tls_version: TlsVersion::TLS1_3,
                     cipher_list: CipherList::ALL,
                     cipher_order: CipherOrder::FORWARD,
@@ -126,6 +252,7 @@ impl Jarm {
                 PacketSpecification {
                     host: host.clone(),
                     port: port.clone(),
+                    server_name: ServerName::Host,
                     tls_version: TlsVersion::TLS1_3,
                     cipher_list: CipherList::ALL,
                     cipher_order: CipherOrder::REVERSE,
@@ -137,6 +264,7 @@ impl Jarm {
                 PacketSpecification {
                     host: host.clone(),
                     port: port.clone(),
+                    server_name: ServerName::Host,
                     tls_version: TlsVersion::TLS1_3,
                     cipher_list: CipherList::NO1_3,
                     cipher_order: CipherOrder::FORWARD,
@@ -148,6 +276,7 @@ impl Jarm {
                 PacketSpecification {
                     host,
                     port,
+                    server_name: ServerName::Host,
                     tls_version: TlsVersion::TLS1_3,
                     cipher_list: CipherList::ALL,
                     cipher_order: CipherOrder::MIDDLE_OUT,
@@ -159,40 +288,110 @@ impl Jarm {
                 //</editor-fold>
             ],
             rng: Box::new(PseudoRng {}),
-            timeout: DEFAULT_TIMEOUT
+            timeout: DEFAULT_TIMEOUT,
+            scan_timeout: None,
+            failure_policy: FailurePolicy::default(),
+            address: None,
+            address_preference: AddressPreference::default(),
+            attempt_delay: DEFAULT_ATTEMPT_DELAY,
+            resolver: Arc::new(SystemResolver),
+            transport: Arc::new(TcpTransport),
+            proxy: None,
         }
     }
 
+    /// Set the name sent in the SNI extension of every probe.
+    /// A chosen name which isn't a valid host name is an `InvalidTarget` error, see `ServerName::validate`.
+    pub fn set_server_name(&mut self, server_name: ServerName) -> Result<(), JarmError> {
+        server_name.validate()?;
+        for spec in &mut self.queue {
+            spec.server_name = server_name.clone();
+        }
+        Ok(())
+    }
+
     pub fn retrieve_parts(&mut self) -> Result<Vec<JarmPart>, JarmError> {
-        let mut parts = Vec::new();
+        Ok(self.retrieve_probes()?.into_iter().map(|probe| probe.part).collect())
+    }
+
+    /// Same as `retrieve_parts`, keeping the alert sent by the server for each probe
+    pub fn retrieve_probes(&mut self) -> Result<Vec<ProbeResult>, JarmError> {
+        self.collect_report(self.stops_on_failure()).into_probes()
+    }
+
+    /// Send every probe and report how each of them went, a failed probe doesn't stop the scan
+    pub fn report(&self) -> JarmReport {
+        self.collect_report(false)
+    }
+
+    fn collect_report(&self, stop_on_failure: bool) -> JarmReport {
+        let route = self.route();
+        match route.pin_connection() {
+            Ok((address, connection)) => self.collect_report_to(&route, stop_on_failure, Ok(address), connection),
+            Err(e) => self.collect_report_to(&route, stop_on_failure, Err(e), None),
+        }
+    }
+
+    /// Send every probe to `pinned_address`, the first one through `connection` if already open
+    fn collect_report_to(&self, route: &Route, stop_on_failure: bool, mut pinned_address: Result<SocketAddr, JarmError>, mut connection: Option<Connection>) -> JarmReport {
+        let mut report = JarmReport {
+            address: pinned_address.as_ref().ok().copied(),
+            failure_policy: self.failure_policy,
+            ..JarmReport::default()
+        };
         for spec in &self.queue {
             let payload = build_packet(spec, self.rng.as_ref());
-
-            // Send packet
-            let url = format!("{}:{}", spec.host, spec.port);
-            let address = resolve(url)?;  // Resolve the ip if needed
-            let mut data = [0_u8; SOCKET_BUFFER as usize];
-            match TcpStream::connect_timeout(&address, self.timeout) {
-                Ok(mut stream) => {
-                    stream.set_read_timeout(Some(self.timeout))?;
-                    stream.set_write_timeout(Some(self.timeout))?;
-                    stream.write_all(&payload)?;
-                    let mut handle = stream.take(SOCKET_BUFFER);
-                    let _read_result = handle.read(&mut data)?;
+            let probe = match (&mut pinned_address, connection.take()) {
+                (Ok(address), Some(connection)) => match route.probe_timeout() {
+                    Some(timeout) => exchange(connection, &payload, timeout),
+                    None => timed_out(*address, Some(connection.connect_time)),
                 },
-                Err(e) => return Err(JarmError::Connection(DetailedError::from(Box::from(e))))
+                (Ok(address), None) => route.send_probe(*address, &payload),
+                // Only the first probe gets the resolution error, the others can't be sent either
+                (Err(e), _) => ProbeReport::failed(None, None, ProbeOutcome::Failed, mem::replace(e, dns_error(&spec.host, DetailedError::default()))),
+            };
+            let failed = probe.error.is_some();
+            report.probes.push(probe);
+            if failed && stop_on_failure {
+                break;
             }
-            let jarm_part = read_packet(Vec::from(data));
-            parts.push(jarm_part);
         }
-        Ok(parts)
+        report
+    }
+
+    fn stops_on_failure(&self) -> bool {
+        self.failure_policy == FailurePolicy::Strict
+    }
+
+    fn route(&self) -> Route {
+        // A host which can't be encoded is kept as is, its resolution fails with the encoding error
+        let (host, port) = self.queue.first()
+            .map(|spec| (domain_to_ascii(&spec.host).unwrap_or_else(|_| spec.host.clone()), spec.port.clone()))
+            .unwrap_or_default();
+        Route {
+            host,
+            port,
+            address: self.address,
+            address_preference: self.address_preference,
+            attempt_delay: self.attempt_delay,
+            timeout: self.timeout,
+            deadline: self.scan_timeout.map(|scan_timeout| Instant::now() + scan_timeout),
+            resolver: Arc::clone(&self.resolver),
+            transport: Arc::clone(&self.transport),
+            proxy: self.proxy.clone(),
+        }
     }
 
     pub fn hash(&mut self) -> Result<String, JarmError> {
         if self.parts.is_empty(){
             self.parts = self.retrieve_parts()?
         }
-        if self.parts.iter().all(|p| p.raw == "|||") {
+        self.compute_hash()
+    }
+
+    /// Compute the jarm hash from the parts already retrieved
+    fn compute_hash(&self) -> Result<String, JarmError> {
+        if self.parts.iter().all(|p| *p == JarmPart::NoResponse) {
             return Ok("0".repeat(62));
         }
 
@@ -200,12 +399,19 @@ impl Jarm {
         let mut alpns_and_ext = String::new();
 
         for part in &self.parts {
-            let components: Vec<&str> = part.raw.split('|').collect();
-            // Custom jarm hash includes a fuzzy hash of the ciphers and versions
-            fuzzy_hash.push_str(&cipher_bytes(components[0]));
-            fuzzy_hash.push(version_byte(components[1]));
-            alpns_and_ext.push_str(components[2]);
-            alpns_and_ext.push_str(components[3]);
+            match part {
+                JarmPart::NoResponse => {
+                    fuzzy_hash.push_str(&cipher_bytes(""));
+                    fuzzy_hash.push(version_byte("")?);
+                }
+                JarmPart::ServerHello { cipher, version, alpn, extensions } => {
+                    // Custom jarm hash includes a fuzzy hash of the ciphers and versions
+                    fuzzy_hash.push_str(&cipher_bytes(&format!("{cipher:04x}")));
+                    fuzzy_hash.push(version_byte(&format!("{version:04x}"))?);
+                    alpns_and_ext.push_str(alpn.as_deref().unwrap_or_default());
+                    alpns_and_ext.push_str(&format_extensions(extensions));
+                }
+            }
         }
 
         // Custom jarm hash has the sha256 of alpns and extensions added to the end
@@ -251,6 +457,8 @@ pub enum TlsVersionSupport {
 pub struct PacketSpecification {
     pub host: String,
     pub port: String,
+    /// Name sent in the SNI extension, independently of the host connected to
+    pub server_name: ServerName,
     pub tls_version: TlsVersion,
     pub cipher_list: CipherList,
     pub cipher_order: CipherOrder,
@@ -402,8 +610,11 @@ pub fn get_extensions(jarm_details: &PacketSpecification, rng: &dyn JarmRng) ->
 }
 
 pub fn extension_server_name(jarm_details: &PacketSpecification) -> Vec<u8> {
+    let Some(server_name) = jarm_details.server_name.for_host(&jarm_details.host) else {
+        return Vec::new();
+    };
     let mut ext_sni = b"\x00\x00".to_vec();
-    let host_length = jarm_details.host.len();
+    let host_length = server_name.len();
     let ext_sni_length = host_length + 5;
     ext_sni.extend(pack_as_unsigned_short(ext_sni_length));
 
@@ -414,7 +625,7 @@ pub fn extension_server_name(jarm_details: &PacketSpecification) -> Vec<u8> {
     let ext_sni_length3 = host_length;
     ext_sni.extend(pack_as_unsigned_short(ext_sni_length3));
 
-    ext_sni.extend(jarm_details.host.bytes());
+    ext_sni.extend(server_name.bytes());
     ext_sni
 }
 
@@ -559,148 +770,158 @@ pub fn supported_versions(jarm_details: &PacketSpecification, rng: &dyn JarmRng)
     ext
 }
 
-pub fn read_packet(data: Vec<u8>) -> JarmPart {
-    if (data[0] != 22) || (data[5] != 2){
-        return JarmPart::new("|||");  // Default jarm
+/// Read the jarm part and the alert of the server response
+pub fn read_probe(data: Vec<u8>) -> Result<ProbeResult, JarmError> {
+    let alert = read_alert(&data);
+    Ok(ProbeResult { part: read_packet(data)?, alert })
+}
+
+pub fn read_packet(data: Vec<u8>) -> Result<JarmPart, JarmError> {
+    if data.first() != Some(&22) || read_u8(&data, 5)? != 2 {
+        return Ok(JarmPart::NoResponse);  // Default jarm
     }
 
-    let mut jarm = String::new();
-    let counter = data[43] as usize;
+    let counter = read_u8(&data, 43)? as usize;
 
     // Find server's selected cipher
-    let start = counter + 44;
-    let end = counter + 45;
-    let selected_cipher = &data[start..=end];
+    let cipher = read_u16(&data, counter + 44)?;
 
     // Find server's selected version
-    let version = &data[9..=10];
-
-    // Format
-    jarm += &*hex::encode(selected_cipher);
-    jarm += "|";
-    jarm += &*hex::encode(version);
-    jarm += "|";
+    let version = read_u16(&data, 9)?;
 
     // Extract extensions
-    let extensions = extract_extension_info(data, counter);
-    jarm += &*extensions;
-    JarmPart { raw: jarm}
+    let (alpn, extensions) = extension_info(&data, counter)?;
+    Ok(JarmPart::ServerHello { cipher, version, alpn, extensions })
 }
 
-// Convert bytes array to u32
+fn parse_error(offset: usize, reason: &'static str) -> JarmError {
+    JarmError::Parse { offset, reason }
+}
+
+fn read_slice(data: &[u8], offset: usize, length: usize) -> Result<&[u8], JarmError> {
+    offset.checked_add(length)
+        .and_then(|end| data.get(offset..end))
+        .ok_or_else(|| parse_error(offset, "truncated server hello"))
+}
+
+fn read_u8(data: &[u8], offset: usize) -> Result<u8, JarmError> {
+    Ok(read_slice(data, offset, 1)?[0])
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, JarmError> {
+    Ok(as_u32_be(read_slice(data, offset, 2)?) as u16)
+}
+
+// Convert bytes array to u32, only the last 4 bytes are kept for longer arrays
 pub fn as_u32_be(array: &[u8]) -> u32 {
-    if array.len() != 2 {
-        eprintln!("array = {array:?}");
-        unimplemented!()  // not needed for now
-    }
-    ((array[0] as u32) << 8) + (array[1] as u32)
+    array.iter().fold(0, |value, byte| (value << 8) + (*byte as u32))
 }
 
-pub fn extract_extension_info(data: Vec<u8>, counter: usize) -> String {
+pub fn extract_extension_info(data: Vec<u8>, counter: usize) -> Result<String, JarmError> {
+    let (alpn, extensions) = extension_info(&data, counter)?;
+    Ok(format!("{}|{}", alpn.unwrap_or_default(), format_extensions(&extensions)))
+}
+
+/// Selected ALPN and extension types of the server hello
+fn extension_info(data: &[u8], counter: usize) -> Result<(Option<String>, Vec<u16>), JarmError> {
     // Error handling
-    if data_has_errors(&data, counter) {
-        return "|".to_string();
+    if data_has_errors(data, counter)? {
+        return Ok((None, Vec::new()));
     }
 
     // Collect types and value
-    let mut count = 49 + (counter as u32);
-    let length_start = counter + 47;
-    let length_end = counter + 48;
-
-    let length_slice: &[u8] = &data[length_start..=length_end];
-    let length = as_u32_be(length_slice);
+    let mut count = 49 + counter;
+    let length = read_u16(data, counter + 47)? as usize;
     let maximum = length + (count - 1);
 
-    let mut types: Vec<&[u8]> = Vec::new();
-    let mut values: Vec<Option<&[u8]>> = Vec::new();
-
+    let mut types = Vec::new();
+    let mut alpn = None;
     while count < maximum {
-        let slice_start = count as usize;
-        types.push(&data[slice_start..slice_start+2]);
-
-        let ext_length_start = (count + 2) as usize;
-        let ext_length_end = ext_length_start + 2;
-        let ext_length_slice: &[u8] = &data[ext_length_start..ext_length_end];
-        let ext_length = as_u32_be(ext_length_slice);
-
-        if ext_length == 0 {
-            values.push(None);  // TODO FIXME
-            count += 4;
-        } else {
-            let value = &data[slice_start + 4..slice_start + 4 + ext_length as usize];
-            values.push(Some(value));
-            count += ext_length + 4
+        let extension_type = read_u16(data, count)?;
+        let extension_length = read_u16(data, count + 2)? as usize;
+        let value = read_slice(data, count + 4, extension_length)?;
+
+        // Read application_layer_protocol_negotiation
+        if extension_type == u16::from_be_bytes(*ALPN_EXTENSION) && alpn.is_none() && !value.is_empty() {
+            alpn = Some(decode_alpn(value, count + 4)?);
         }
+        types.push(extension_type);
+        count += extension_length + 4;
     }
 
-    // Read application_layer_protocol_negotiation
-    let alpn = find_extension(&types, values);
-
-    let formatted_types = add_formatting_hyphen(&types);
-    format!("{alpn}|{formatted_types}")
+    Ok((alpn.filter(|a| !a.is_empty()), types))
 }
 
-fn data_has_errors(data: &[u8], counter: usize) -> bool {
+fn data_has_errors(data: &[u8], counter: usize) -> Result<bool, JarmError> {
     let length_start = counter + 47;
-    if data[length_start] == 11 {
-        return true;
+    if read_u8(data, length_start)? == 11 {
+        return Ok(true);
     }
-    if data[(counter + 50)..(counter + 53)] == b"\x0e\xac\x0b".to_vec() ||
-        data[(counter + 82)..(counter + 85)] == b"\x0f\xf0\x0b".to_vec() {
-        return true;
+    if data.get((counter + 50)..(counter + 53)) == Some(b"\x0e\xac\x0b") ||
+        data.get((counter + 82)..(counter + 85)) == Some(b"\x0f\xf0\x0b") {
+        return Ok(true);
     }
-    let server_hello_length_slice: &[u8] = &data[3..5];
-    let server_hello_length = as_u32_be(server_hello_length_slice);
-    if (counter as u32) + 42 >= server_hello_length {
-        return true;
+    let server_hello_length = read_u16(data, 3)? as usize;
+    if counter + 42 >= server_hello_length {
+        return Ok(true);
     }
-    false
+    Ok(false)
 }
 
+/// Decode the first protocol of an ALPN extension value located at `offset`
+fn decode_alpn(value: &[u8], offset: usize) -> Result<String, JarmError> {
+    // Skip the protocol list length and the protocol length
+    let protocol = value.get(3..).unwrap_or_default();
+    match std::str::from_utf8(protocol) {
+        Ok(s) => Ok(s.to_string()),
+        Err(e) => Err(parse_error(offset + 3 + e.valid_up_to(), "invalid UTF-8 ALPN")),
+    }
+}
+
+/// Format the extension types as in the raw jarm part: `0000-0017-ff01`
+fn format_extensions(extensions: &[u16]) -> String {
+    let types_hex_encoded: Vec<String> = extensions.iter().map(|e| format!("{e:04x}")).collect();
+    types_hex_encoded.join("-")
+}
+
+#[deprecated(since = "0.4.0", note = "the extensions of a `JarmPart::ServerHello` are typed, its `Display` formats them")]
 pub fn add_formatting_hyphen(types: &[&[u8]]) -> String {
     let types_hex_encoded: Vec<String> = types.iter().map(hex::encode).collect();
     types_hex_encoded.join("-")
 }
 
 
-pub fn find_extension(types: &[&[u8]], values: Vec<Option<&[u8]>>) -> String {
-    let mut i = 0;
-    while i < types.len() {
-        if types.get(i).unwrap() == ALPN_EXTENSION {
-            let x = values.get(i).unwrap();
-            match x {
-                None => {}
-                Some(y) => {
-                    match std::str::from_utf8(&y[3..]) {
-                        Ok(s) => return s.to_string(),
-                        Err(e) => panic!("Invalid UTF-8 sequence: {e}"),
-                    };
-                }
-            }
+/// Offsets of the parse errors are relative to the extension value
+#[deprecated(since = "0.4.0", note = "use the `alpn` of the `JarmPart::ServerHello` returned by `read_packet`")]
+pub fn find_extension(types: &[&[u8]], values: Vec<Option<&[u8]>>) -> Result<String, JarmError> {
+    for (extension_type, value) in types.iter().zip(values) {
+        if *extension_type == ALPN_EXTENSION && let Some(value) = value {
+            return decode_alpn(value, 0);
         }
-        i += 1
     }
-    "".to_string()
+    Ok("".to_string())
 }
 
+/// Cipher suites the cipher part of a jarm hash refers to, by their 1-based position
+pub(crate) const JARM_CIPHERS: [&[u8; 2]; 69] = [
+    b"\x00\x04", b"\x00\x05", b"\x00\x07", b"\x00\x0a", b"\x00\x16", b"\x00\x2f", b"\x00\x33", b"\x00\x35",
+    b"\x00\x39", b"\x00\x3c", b"\x00\x3d", b"\x00\x41", b"\x00\x45", b"\x00\x67", b"\x00\x6b", b"\x00\x84",
+    b"\x00\x88", b"\x00\x9a", b"\x00\x9c", b"\x00\x9d", b"\x00\x9e", b"\x00\x9f", b"\x00\xba", b"\x00\xbe",
+    b"\x00\xc0", b"\x00\xc4", b"\xc0\x07", b"\xc0\x08", b"\xc0\x09", b"\xc0\x0a", b"\xc0\x11", b"\xc0\x12",
+    b"\xc0\x13", b"\xc0\x14", b"\xc0\x23", b"\xc0\x24", b"\xc0\x27", b"\xc0\x28", b"\xc0\x2b", b"\xc0\x2c",
+    b"\xc0\x2f", b"\xc0\x30", b"\xc0\x60", b"\xc0\x61", b"\xc0\x72", b"\xc0\x73", b"\xc0\x76", b"\xc0\x77",
+    b"\xc0\x9c", b"\xc0\x9d", b"\xc0\x9e", b"\xc0\x9f", b"\xc0\xa0", b"\xc0\xa1", b"\xc0\xa2", b"\xc0\xa3",
+    b"\xc0\xac", b"\xc0\xad", b"\xc0\xae", b"\xc0\xaf", b"\xcc\x13", b"\xcc\x14", b"\xcc\xa8", b"\xcc\xa9",
+    b"\x13\x01", b"\x13\x02", b"\x13\x03", b"\x13\x04", b"\x13\x05",
+];
+
 pub fn cipher_bytes(cipher: &str) -> String {
     if cipher.is_empty() {
         return "00".to_string()
     }
 
-    let list = vec![
-        b"\x00\x04", b"\x00\x05", b"\x00\x07", b"\x00\x0a", b"\x00\x16", b"\x00\x2f", b"\x00\x33", b"\x00\x35",
-        b"\x00\x39", b"\x00\x3c", b"\x00\x3d", b"\x00\x41", b"\x00\x45", b"\x00\x67", b"\x00\x6b", b"\x00\x84",
-        b"\x00\x88", b"\x00\x9a", b"\x00\x9c", b"\x00\x9d", b"\x00\x9e", b"\x00\x9f", b"\x00\xba", b"\x00\xbe",
-        b"\x00\xc0", b"\x00\xc4", b"\xc0\x07", b"\xc0\x08", b"\xc0\x09", b"\xc0\x0a", b"\xc0\x11", b"\xc0\x12",
-        b"\xc0\x13", b"\xc0\x14", b"\xc0\x23", b"\xc0\x24", b"\xc0\x27", b"\xc0\x28", b"\xc0\x2b", b"\xc0\x2c",
-        b"\xc0\x2f", b"\xc0\x30", b"\xc0\x60", b"\xc0\x61", b"\xc0\x72", b"\xc0\x73", b"\xc0\x76", b"\xc0\x77",
-        b"\xc0\x9c", b"\xc0\x9d", b"\xc0\x9e", b"\xc0\x9f", b"\xc0\xa0", b"\xc0\xa1", b"\xc0\xa2", b"\xc0\xa3",
-        b"\xc0\xac", b"\xc0\xad", b"\xc0\xae", b"\xc0\xaf", b"\xcc\x13", b"\xcc\x14", b"\xcc\xa8", b"\xcc\xa9",
-        b"\x13\x01", b"\x13\x02", b"\x13\x03", b"\x13\x04", b"\x13\x05"
-    ];
-    let count = match list.iter().position(|&bytes| hex::encode(bytes) == cipher) {
-        None => { list.len() + 1 }
+    let count = match JARM_CIPHERS.iter().position(|&bytes| hex::encode(bytes) == cipher) {
+        None => { JARM_CIPHERS.len() + 1 }
         Some(index) => { index + 1 }
     };
 
@@ -708,34 +929,128 @@ pub fn cipher_bytes(cipher: &str) -> String {
     hex_value.get(hex_value.len() - 2..hex_value.len()).unwrap().to_string()
 }
 
-pub fn version_byte(version: &str) -> char {
+pub fn version_byte(version: &str) -> Result<char, JarmError> {
     if version.is_empty() {
-        return '0';
+        return Ok('0');
     }
-    let option = "abcdef".to_string();
+    let option = "abcdef";
     let version_index: usize = 3;
-    let count: usize = match version.get(version_index..version_index+1) {
-        None => { panic!("version not expected {version:?}")}
-        Some(str_count) => { usize::from_str(str_count).unwrap() }
-    };
-    option.chars().nth(count).unwrap()
+    version.get(version_index..version_index+1)
+        .and_then(|str_count| usize::from_str(str_count).ok())
+        .and_then(|count| option.chars().nth(count))
+        .ok_or_else(|| parse_error(version_index, "unexpected version"))
 }
 
 
-/// Resolve the given url to an ip
-/// the first ip found is returned, else an error is raised.
-fn resolve(url: String) -> Result<SocketAddr, JarmError> {
-    let mut ips = match url.to_socket_addrs() {
-        Ok(address) => address,
-        Err(e) => {
-            let error = DetailedError::from(Box::from(e));
-            return Err(JarmError::DnsResolve(error))
-        },
-    };
-    if let Some(address) = ips.next() {
-        Ok(address)
-    } else {
-        Err(JarmError::DnsResolve(DetailedError::default()))
+/// How the probes of a scan reach the server, detached from the `Jarm` so it can be moved to other threads
+#[derive(Clone)]
+struct Route {
+    host: String,
+    port: String,
+    address: Option<SocketAddr>,
+    address_preference: AddressPreference,
+    attempt_delay: Duration,
+    timeout: Duration,
+    /// End of the scan timeout, if any
+    deadline: Option<Instant>,
+    resolver: Arc<dyn JarmResolver>,
+    transport: Arc<dyn JarmTransport>,
+    proxy: Option<Proxy>,
+}
+
+impl Route {
+    /// Address every probe of the scan is sent to: the proxy or `address` if set, else the first resolved address
+    /// accepting a connection, tried as in RFC 8305. That connection is returned to be used by the first probe.
+    /// If no address accepts a connection, the probes are sent to the first one to report their failure.
+    fn pin_connection(&self) -> Result<(SocketAddr, Option<Connection>), JarmError> {
+        match (&self.proxy, self.address) {
+            (Some(proxy), _) => return Ok((proxy.address(), None)),
+            (None, Some(address)) => return Ok((address, None)),
+            (None, None) => {},
+        }
+        let addresses = sort_addresses(&resolve(self.resolver.as_ref(), &self.host, &self.port)?, self.address_preference);
+        match addresses.as_slice() {
+            [] => Err(dns_error(&self.host, DetailedError::default())),
+            [address] => Ok((*address, None)),
+            [first, ..] => match self.probe_timeout().and_then(|timeout| connect_first(&self.transport, &addresses, self.attempt_delay, timeout)) {
+                Some(connection) => Ok((connection.address, Some(connection))),
+                None => Ok((*first, None)),
+            },
+        }
+    }
+
+    /// Open a new connection to `address` to send the client hello, or to the server through the proxy
+    fn send_probe(&self, address: SocketAddr, payload: &[u8]) -> ProbeReport {
+        let Some(timeout) = self.probe_timeout() else {
+            return timed_out(address, None);
+        };
+        let start = Instant::now();
+        let stream = match &self.proxy {
+            Some(proxy) => self.connect_through(proxy, timeout),
+            None => self.transport.connect(address, timeout)
+                .map_err(|e| (ProbeOutcome::from(&e), JarmError::Connection(DetailedError::from(Box::from(e))))),
+        };
+        let connect_time = start.elapsed();
+        match (stream, self.probe_timeout()) {
+            (Ok(stream), Some(timeout)) => exchange(Connection { address, stream, connect_time }, payload, timeout),
+            (Ok(_), None) => timed_out(address, Some(connect_time)),
+            (Err((outcome, error)), _) => ProbeReport::failed(Some(address), None, outcome, error),
+        }
+    }
+
+    /// Tunnel to `address` if set, else to the host which is resolved by the proxy
+    fn connect_through(&self, proxy: &Proxy, timeout: Duration) -> Result<Box<dyn JarmStream>, (ProbeOutcome, JarmError)> {
+        let (host, port) = self.tunnel_target()?;
+        proxy.connect(self.transport.as_ref(), &host, port, timeout).map_err(|e| (ProbeOutcome::Failed, e))
+    }
+
+    /// Timeout of the next connection or exchange, cut to what is left of the scan timeout.
+    /// `None` once the scan timeout is reached.
+    fn probe_timeout(&self) -> Option<Duration> {
+        match self.deadline {
+            Some(deadline) => {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                (!remaining.is_zero()).then(|| remaining.min(self.timeout))
+            },
+            None => Some(self.timeout),
+        }
+    }
+
+    /// Host and port the proxy is asked a tunnel to
+    fn tunnel_target(&self) -> Result<(String, u16), (ProbeOutcome, JarmError)> {
+        match self.address {
+            Some(address) => Ok((address.ip().to_string(), address.port())),
+            None => match self.port.parse() {
+                Ok(port) => Ok((self.host.clone(), port)),
+                Err(e) => Err((ProbeOutcome::Failed, dns_error(&self.host, DetailedError::from(Box::from(e))))),
+            },
+        }
+    }
+}
+
+/// Report of a probe which couldn't be sent or answered before the scan timeout
+fn timed_out(address: SocketAddr, connect_time: Option<Duration>) -> ProbeReport {
+    let error = io::Error::new(ErrorKind::TimedOut, "scan timeout reached");
+    ProbeReport::failed(Some(address), connect_time, ProbeOutcome::Timeout, JarmError::Io(DetailedError::from(Box::from(error))))
+}
+
+/// Send the client hello through the connection and read the answer
+fn exchange(connection: Connection, payload: &[u8], timeout: Duration) -> ProbeReport {
+    let Connection { address, mut stream, connect_time } = connection;
+    let exchange = stream.write_all(payload)
+        .map(|_| Instant::now())
+        .and_then(|sent| Ok((sent, read_response(stream.as_mut(), timeout)?)));
+    match exchange {
+        Ok((sent, response)) => ProbeReport::answered(address, connect_time, sent, response),
+        Err(e) => ProbeReport::failed(Some(address), Some(connect_time), ProbeOutcome::from(&e), e.into()),
+    }
+}
+
+/// Every address the host resolves to, `port` being the port of a `PacketSpecification`
+fn resolve(resolver: &dyn JarmResolver, host: &str, port: &str) -> Result<Vec<SocketAddr>, JarmError> {
+    match port.parse() {
+        Ok(port) => resolver.resolve(&domain_to_ascii(host)?, port),
+        Err(e) => Err(dns_error(host, DetailedError::from(Box::from(e)))),
     }
 }
 
@@ -792,19 +1107,95 @@ impl JarmRng for PseudoRng {  // Real Rng used outside of tests
 
 #[cfg(test)]
 mod tests {
+    use std::io::Read;
+    use std::net::{SocketAddr, TcpListener};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU8, Ordering};
+    use std::thread;
     use rstest::*;
-    use crate::resolve;
+    use crate::{resolve, Jarm, TestRng};
     use crate::error::JarmError;
+    use crate::resolver::{JarmResolver, StaticResolver, SystemResolver};
+
+    /// Resolver giving a new address on every call
+    struct StubResolver {
+        calls: AtomicU8,
+    }
+
+    impl JarmResolver for StubResolver {
+        fn resolve(&self, _host: &str, port: u16) -> Result<Vec<SocketAddr>, JarmError> {
+            let call = self.calls.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok(vec![SocketAddr::from(([127, 0, 0, call], port))])
+        }
+    }
+
+    /// Listener on 127.0.0.1 closing every connection once the client hello is received
+    fn spawn_listener() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let _ = stream.unwrap().read(&mut [0_u8; 1]);  // then close, the probe fails
+            }
+        });
+        address
+    }
 
     #[rstest]
-    #[case("invalid_url")]
-    #[case("google.com")]  // missing port
-    fn test_dns_resolve_error(#[case] invalid_url: String) {
-        let expected_error = "invalid socket address";
-        let error = resolve(invalid_url).err().unwrap();
-        if let JarmError::DnsResolve(err) = error {
-            let underlying_error = err.underlying_error.unwrap();
+    #[case("invalid_port", "invalid digit found in string")]
+    #[case("", "cannot parse integer from empty string")]  // missing port
+    fn test_dns_resolve_error(#[case] invalid_port: String, #[case] expected_error: &str) {
+        let jarm = Jarm::new("localhost".to_string(), invalid_port);
+        let error = resolve(&SystemResolver, &jarm.queue[0].host, &jarm.queue[0].port).err().unwrap();
+        if let JarmError::DnsResolve { name, error } = error {
+            assert_eq!(name, "localhost");
+            let underlying_error = error.underlying_error.unwrap();
             assert_eq!(underlying_error.to_string(), expected_error);
         } else { panic!("unexpected type") }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_address_resolved_once_per_scan() {
+        let port = spawn_listener().port();
+        // Only the first address is listening, a new resolution would connect to a closed one
+        let resolver = Arc::new(StubResolver { calls: AtomicU8::new(0) });
+        let mut jarm = Jarm::new("localhost".to_string(), port.to_string());
+        jarm.rng = Box::new(TestRng {});
+        jarm.resolver = resolver.clone();
+
+        let report = jarm.report();
+
+        assert_eq!(resolver.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(report.address, Some(SocketAddr::from(([127, 0, 0, 1], port))));
+        // Every probe reached the listening address
+        assert!(report.probes.iter().all(|p| p.address == report.address && p.connect_time.is_some()));
+    }
+
+    #[test]
+    fn test_explicit_address_skips_resolution() {
+        let address = SocketAddr::from(([127, 0, 0, 1], 1));
+        let mut jarm = Jarm::new("unknown.example".to_string(), "443".to_string());
+        jarm.resolver = Arc::new(StaticResolver::new());  // fails for any host
+        jarm.address = Some(address);
+
+        let (pinned_address, _) = jarm.route().pin_connection().unwrap();
+
+        assert_eq!(pinned_address, address);
+    }
+
+    #[test]
+    fn test_unreachable_address_falls_back_to_next() {
+        let listening = spawn_listener();
+        let mut jarm = Jarm::new("jarm.example".to_string(), listening.port().to_string());
+        jarm.rng = Box::new(TestRng {});
+        // Nothing listens on 127.0.0.2
+        jarm.resolver = Arc::new(StaticResolver::new()
+            .with_host("jarm.example", [127, 0, 0, 2].into())
+            .with_host("jarm.example", listening.ip()));
+
+        let report = jarm.report();
+
+        assert_eq!(report.address, Some(listening));
+        assert!(report.probes.iter().all(|p| p.address == Some(listening) && p.connect_time.is_some()));
+    }
+}
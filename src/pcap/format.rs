@@ -0,0 +1,130 @@
+use crate::error::{DetailedError, JarmError};
+
+const PCAP_MAGIC_MICRO: u32 = 0xa1b2c3d4;
+const PCAP_MAGIC_NANO: u32 = 0xa1b23c4d;
+const PCAPNG_SECTION_HEADER: u32 = 0x0a0d0d0a;
+const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1a2b3c4d;
+const PCAPNG_INTERFACE_DESCRIPTION: u32 = 0x00000001;
+const PCAPNG_OBSOLETE_PACKET: u32 = 0x00000002;
+const PCAPNG_SIMPLE_PACKET: u32 = 0x00000003;
+const PCAPNG_ENHANCED_PACKET: u32 = 0x00000006;
+
+/// A captured frame and the link type of the interface it was captured on
+pub struct Frame<'a> {
+    pub link_type: u32,
+    pub data: &'a [u8],
+}
+
+pub fn capture_error(reason: &str) -> JarmError {
+    JarmError::Capture(DetailedError::from(Box::from(reason)))
+}
+
+#[derive(Clone, Copy)]
+enum Endianness {
+    Little,
+    Big,
+}
+
+impl Endianness {
+    fn u16(self, data: &[u8], offset: usize) -> Option<u16> {
+        let bytes: [u8; 2] = data.get(offset..offset + 2)?.try_into().ok()?;
+        Some(match self {
+            Endianness::Little => u16::from_le_bytes(bytes),
+            Endianness::Big => u16::from_be_bytes(bytes),
+        })
+    }
+
+    fn u32(self, data: &[u8], offset: usize) -> Option<u32> {
+        let bytes: [u8; 4] = data.get(offset..offset + 4)?.try_into().ok()?;
+        Some(match self {
+            Endianness::Little => u32::from_le_bytes(bytes),
+            Endianness::Big => u32::from_be_bytes(bytes),
+        })
+    }
+}
+
+/// Read every frame of a pcap or pcapng capture
+pub fn read_frames(capture: &[u8]) -> Result<Vec<Frame<'_>>, JarmError> {
+    let magic = Endianness::Little.u32(capture, 0).ok_or_else(|| capture_error("capture is too short"))?;
+    match magic {
+        PCAP_MAGIC_MICRO | PCAP_MAGIC_NANO => read_pcap(capture, Endianness::Little),
+        m if m.swap_bytes() == PCAP_MAGIC_MICRO || m.swap_bytes() == PCAP_MAGIC_NANO => read_pcap(capture, Endianness::Big),
+        PCAPNG_SECTION_HEADER => read_pcapng(capture),
+        _ => Err(capture_error("unknown capture format")),
+    }
+}
+
+fn read_pcap(capture: &[u8], endianness: Endianness) -> Result<Vec<Frame<'_>>, JarmError> {
+    let link_type = endianness.u32(capture, 20).ok_or_else(|| capture_error("truncated pcap header"))?;
+    let mut frames = Vec::new();
+    let mut offset = 24;
+    while offset < capture.len() {
+        let captured_length = endianness.u32(capture, offset + 8)
+            .ok_or_else(|| capture_error("truncated pcap record header"))? as usize;
+        let start = offset + 16;
+        let data = capture.get(start..start + captured_length)
+            .ok_or_else(|| capture_error("truncated pcap record"))?;
+        frames.push(Frame { link_type, data });
+        offset = start + captured_length;
+    }
+    Ok(frames)
+}
+
+fn read_pcapng(capture: &[u8]) -> Result<Vec<Frame<'_>>, JarmError> {
+    let mut frames = Vec::new();
+    let mut endianness = Endianness::Little;
+    let mut interfaces: Vec<u32> = Vec::new();
+    let mut offset = 0;
+    while offset < capture.len() {
+        let block_type = endianness.u32(capture, offset).ok_or_else(|| capture_error("truncated pcapng block"))?;
+        if block_type == PCAPNG_SECTION_HEADER {
+            // The byte order magic tells the endianness of the whole section
+            endianness = match Endianness::Little.u32(capture, offset + 8) {
+                Some(PCAPNG_BYTE_ORDER_MAGIC) => Endianness::Little,
+                Some(m) if m.swap_bytes() == PCAPNG_BYTE_ORDER_MAGIC => Endianness::Big,
+                _ => return Err(capture_error("invalid pcapng byte order magic")),
+            };
+            interfaces.clear();
+        }
+        let block_length = endianness.u32(capture, offset + 4)
+            .ok_or_else(|| capture_error("truncated pcapng block"))? as usize;
+        if block_length < 12 || !block_length.is_multiple_of(4) {
+            return Err(capture_error("invalid pcapng block length"));
+        }
+        let block = capture.get(offset..offset + block_length)
+            .ok_or_else(|| capture_error("truncated pcapng block"))?;
+        let body = &block[8..block_length - 4];
+
+        match block_type {
+            PCAPNG_INTERFACE_DESCRIPTION => {
+                let link_type = endianness.u16(body, 0).ok_or_else(|| capture_error("truncated interface block"))?;
+                interfaces.push(link_type as u32);
+            }
+            PCAPNG_ENHANCED_PACKET | PCAPNG_OBSOLETE_PACKET => {
+                let (interface, captured_length) = if block_type == PCAPNG_ENHANCED_PACKET {
+                    (endianness.u32(body, 0), endianness.u32(body, 12))
+                } else {
+                    (endianness.u16(body, 0).map(u32::from), endianness.u32(body, 12))
+                };
+                let (interface, captured_length) = interface.zip(captured_length)
+                    .ok_or_else(|| capture_error("truncated packet block"))?;
+                let link_type = *interfaces.get(interface as usize)
+                    .ok_or_else(|| capture_error("packet block references an unknown interface"))?;
+                let data = body.get(20..20 + captured_length as usize)
+                    .ok_or_else(|| capture_error("truncated packet block"))?;
+                frames.push(Frame { link_type, data });
+            }
+            PCAPNG_SIMPLE_PACKET => {
+                let link_type = *interfaces.first()
+                    .ok_or_else(|| capture_error("packet block references an unknown interface"))?;
+                let original_length = endianness.u32(body, 0)
+                    .ok_or_else(|| capture_error("truncated packet block"))? as usize;
+                let data = &body[4..];
+                frames.push(Frame { link_type, data: &data[..original_length.min(data.len())] });
+            }
+            _ => {}  // other blocks (statistics, name resolution...) are not needed
+        }
+        offset += block_length;
+    }
+    Ok(frames)
+}
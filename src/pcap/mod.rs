@@ -0,0 +1,172 @@
+//! Offline fingerprinting from packet captures.
+//!
+//! The tcp streams of a pcap or pcapng capture are reassembled, each client hello is matched
+//! against the probes of the `Jarm` queue and the server response goes through `read_packet`,
+//! so a capture of a jarm scan gives back the same hash as the live scan.
+
+mod format;
+mod tcp;
+
+use std::net::SocketAddr;
+use crate::error::JarmError;
+use crate::pcap::format::read_frames;
+use crate::pcap::tcp::{decode_frame, reassemble, Connection};
+use crate::alert::{read_alert, TlsAlert};
+use crate::iana::is_grease;
+use crate::response::{ResponseEnding, ServerResponse};
+use crate::{build_packet, read_packet, Jarm, JarmPart, TestRng};
+
+const EXTENSION_SERVER_NAME: u16 = 0x0000;
+const EXTENSION_KEY_SHARE: u16 = 0x0033;
+const EXTENSION_SUPPORTED_VERSIONS: u16 = 0x002b;
+
+/// A client hello and the server response found in a capture
+pub struct CapturedExchange {
+    pub client: SocketAddr,
+    pub server: SocketAddr,
+    /// Index in the `Jarm` queue of the probe matching the client hello, `None` for other client hellos
+    pub probe: Option<usize>,
+    pub part: JarmPart,
+    /// Alert sent by the server instead of a server hello
+    pub alert: Option<TlsAlert>,
+}
+
+/// The jarm probes sent to a server during one scan found in a capture
+pub struct CapturedJarm {
+    pub server: SocketAddr,
+    /// Parts in the `Jarm` queue order, `None` for the probes missing from the capture
+    pub parts: Vec<Option<JarmPart>>,
+}
+
+impl CapturedJarm {
+    /// Jarm hash of the scan, only available if all the probes were captured
+    pub fn hash(self) -> Result<Option<String>, JarmError> {
+        let Some(parts) = self.parts.into_iter().collect::<Option<Vec<JarmPart>>>() else {
+            return Ok(None);
+        };
+        Jarm { parts, ..Jarm::default() }.compute_hash().map(Some)
+    }
+}
+
+/// Extract every client hello / server response exchange of a pcap or pcapng capture
+pub fn read_exchanges(capture: &[u8]) -> Result<Vec<CapturedExchange>, JarmError> {
+    let frames = read_frames(capture)?;
+    let segments = frames.iter().filter_map(decode_frame).collect();
+    let probe_signatures = probe_signatures();
+
+    let exchanges = reassemble(segments).into_iter().filter_map(|connection| {
+        let Connection { initiator, responder, initiator_data, responder_data } = connection;
+        // The capture may start after the connection was opened, the client is the one sending the client hello
+        let (client, server, signature, response) = match ClientHelloSignature::parse(&initiator_data) {
+            Some(signature) => (initiator, responder, signature, responder_data),
+            None => (responder, initiator, ClientHelloSignature::parse(&responder_data)?, initiator_data),
+        };
+
+        // Same data as the one read from the socket during a live scan, the capture ending with the connection
+        let mut response = ServerResponse::from(response);
+        response.ending = ResponseEnding::Closed;
+        let data = response.into_packet();
+        Some(CapturedExchange {
+            client,
+            server,
+            probe: probe_signatures.iter().position(|s| *s == signature),
+            alert: data.as_deref().ok().and_then(read_alert),
+            // As in the reference implementation, a response which can't be parsed counts as no response
+            part: data.and_then(read_packet).unwrap_or(JarmPart::NoResponse),
+        })
+    }).collect();
+    Ok(exchanges)
+}
+
+/// Rebuild the jarm scans of a pcap or pcapng capture.
+/// Probes are grouped by server, a new scan starts when a probe is seen again for the same server.
+pub fn read_jarms(capture: &[u8]) -> Result<Vec<CapturedJarm>, JarmError> {
+    let probe_count = Jarm::default().queue.len();
+    let mut jarms: Vec<CapturedJarm> = Vec::new();
+    for exchange in read_exchanges(capture)? {
+        let Some(probe) = exchange.probe else { continue };
+        let existing = jarms.iter_mut().rev()
+            .find(|jarm| jarm.server == exchange.server);
+        match existing {
+            Some(jarm) if jarm.parts[probe].is_none() => jarm.parts[probe] = Some(exchange.part),
+            _ => {
+                let mut parts: Vec<Option<JarmPart>> = (0..probe_count).map(|_| None).collect();
+                parts[probe] = Some(exchange.part);
+                jarms.push(CapturedJarm { server: exchange.server, parts });
+            }
+        }
+    }
+    Ok(jarms)
+}
+
+/// Signatures of the probes of the `Jarm` queue, in the same order
+fn probe_signatures() -> Vec<ClientHelloSignature> {
+    Jarm::default().queue.iter()
+        .map(|spec| ClientHelloSignature::parse(&build_packet(spec, &TestRng {})).unwrap())
+        .collect()
+}
+
+/// Parts of a client hello identifying a jarm probe.
+/// Random values (random, session id, key share, grease) and the server name are left out.
+#[derive(PartialEq, Eq)]
+struct ClientHelloSignature {
+    record_version: u16,
+    client_version: u16,
+    ciphers: Vec<u16>,
+    grease: bool,
+    extensions: Vec<(u16, Vec<u8>)>,
+}
+
+impl ClientHelloSignature {
+    fn parse(data: &[u8]) -> Option<ClientHelloSignature> {
+        let u16_at = |offset: usize| -> Option<u16> {
+            Some(u16::from_be_bytes(data.get(offset..offset + 2)?.try_into().ok()?))
+        };
+        if *data.first()? != 0x16 || *data.get(5)? != 0x01 {
+            return None;  // not a client hello
+        }
+        let record_version = u16_at(1)?;
+        let client_version = u16_at(9)?;
+        let session_id_length = *data.get(43)? as usize;
+        let mut offset = 44 + session_id_length;
+
+        let ciphers_length = u16_at(offset)? as usize;
+        let mut grease = false;
+        let mut ciphers = Vec::new();
+        for cipher_offset in (offset + 2..offset + 2 + ciphers_length).step_by(2) {
+            let cipher = u16_at(cipher_offset)?;
+            if is_grease(cipher) {
+                grease = true;
+            } else {
+                ciphers.push(cipher);
+            }
+        }
+        offset += 2 + ciphers_length;
+        let compression_length = *data.get(offset)? as usize;
+        offset += 1 + compression_length;
+
+        let extensions_end = offset + 2 + u16_at(offset)? as usize;
+        offset += 2;
+        let mut extensions = Vec::new();
+        while offset < extensions_end {
+            let extension_type = u16_at(offset)?;
+            let length = u16_at(offset + 2)? as usize;
+            let value = data.get(offset + 4..offset + 4 + length)?;
+            offset += 4 + length;
+            if is_grease(extension_type) {
+                grease = true;
+                continue;
+            }
+            let value = match extension_type {
+                EXTENSION_SERVER_NAME | EXTENSION_KEY_SHARE => Vec::new(),
+                EXTENSION_SUPPORTED_VERSIONS => value.get(1..).unwrap_or_default().chunks_exact(2)
+                    .filter(|version| !is_grease(u16::from_be_bytes([version[0], version[1]])))
+                    .flatten().copied().collect(),
+                _ => value.to_vec(),
+            };
+            extensions.push((extension_type, value));
+        }
+
+        Some(ClientHelloSignature { record_version, client_version, ciphers, grease, extensions })
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use crate::pcap::format::Frame;
+
+const LINKTYPE_NULL: u32 = 0;
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_IPV4: u32 = 228;
+const LINKTYPE_IPV6: u32 = 229;
+const LINKTYPE_LINUX_SLL2: u32 = 276;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86dd;
+const ETHERTYPE_VLAN: u16 = 0x8100;
+const IP_PROTOCOL_TCP: u8 = 6;
+
+const TCP_FLAG_SYN: u8 = 0x02;
+
+pub struct TcpSegment<'a> {
+    pub source: SocketAddr,
+    pub destination: SocketAddr,
+    pub sequence: u32,
+    pub syn: bool,
+    pub payload: &'a [u8],
+}
+
+fn u16_be(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(offset..offset + 2)?.try_into().ok()?))
+}
+
+fn u32_be(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(offset..offset + 4)?.try_into().ok()?))
+}
+
+/// Decode the tcp segment carried by a frame, `None` if the frame is not a tcp packet
+pub fn decode_frame<'a>(frame: &Frame<'a>) -> Option<TcpSegment<'a>> {
+    let data = frame.data;
+    let ip_packet = match frame.link_type {
+        LINKTYPE_ETHERNET => {
+            let mut ethertype = u16_be(data, 12)?;
+            let mut offset = 14;
+            while ethertype == ETHERTYPE_VLAN {
+                ethertype = u16_be(data, offset + 2)?;
+                offset += 4;
+            }
+            matches!(ethertype, ETHERTYPE_IPV4 | ETHERTYPE_IPV6).then_some(data.get(offset..)?)?
+        }
+        LINKTYPE_LINUX_SLL => data.get(16..)?,
+        LINKTYPE_LINUX_SLL2 => data.get(20..)?,
+        LINKTYPE_NULL => data.get(4..)?,
+        LINKTYPE_RAW | LINKTYPE_IPV4 | LINKTYPE_IPV6 => data,
+        _ => return None,
+    };
+    decode_ip(ip_packet)
+}
+
+fn decode_ip(packet: &[u8]) -> Option<TcpSegment<'_>> {
+    match packet.first()? >> 4 {
+        4 => {
+            let source = IpAddr::V4(Ipv4Addr::from(u32_be(packet, 12)?));
+            let destination = IpAddr::V4(Ipv4Addr::from(u32_be(packet, 16)?));
+            let header_length = ((packet[0] & 0x0f) as usize) * 4;
+            let total_length = u16_be(packet, 2)? as usize;
+            let fragment = u16_be(packet, 6)?;
+            if fragment & 0x3fff != 0 || packet[9] != IP_PROTOCOL_TCP {
+                return None;  // fragments are not reassembled
+            }
+            // The total length drops the ethernet padding
+            let tcp = packet.get(header_length..total_length.min(packet.len()))?;
+            decode_tcp(source, destination, tcp)
+        }
+        6 => {
+            let payload_length = u16_be(packet, 4)? as usize;
+            let source = IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(packet.get(8..24)?).ok()?));
+            let destination = IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(packet.get(24..40)?).ok()?));
+            let mut next_header = packet[6];
+            let mut offset = 40;
+            // Skip hop-by-hop, routing and destination options extension headers
+            while matches!(next_header, 0 | 43 | 60) {
+                next_header = *packet.get(offset)?;
+                offset += (*packet.get(offset + 1)? as usize + 1) * 8;
+            }
+            if next_header != IP_PROTOCOL_TCP {
+                return None;
+            }
+            let tcp = packet.get(offset..(40 + payload_length).min(packet.len()))?;
+            decode_tcp(source, destination, tcp)
+        }
+        _ => None,
+    }
+}
+
+fn decode_tcp(source: IpAddr, destination: IpAddr, segment: &[u8]) -> Option<TcpSegment<'_>> {
+    let data_offset = ((*segment.get(12)? >> 4) as usize) * 4;
+    Some(TcpSegment {
+        source: SocketAddr::new(source, u16_be(segment, 0)?),
+        destination: SocketAddr::new(destination, u16_be(segment, 2)?),
+        sequence: u32_be(segment, 4)?,
+        syn: segment.get(13)? & TCP_FLAG_SYN != 0,
+        payload: segment.get(data_offset..)?,
+    })
+}
+
+/// Data sent in one direction of a tcp connection
+#[derive(Default)]
+struct HalfStream {
+    initial_sequence: Option<u32>,
+    segments: Vec<(u32, Vec<u8>)>,
+}
+
+impl HalfStream {
+    /// Reassemble the segments in sequence order, stopping at the first missing segment
+    fn reassemble(&self) -> Vec<u8> {
+        let Some(base) = self.initial_sequence.or_else(|| self.segments.first().map(|s| s.0)) else {
+            return Vec::new();
+        };
+        let mut segments: Vec<(i64, &[u8])> = self.segments.iter()
+            .map(|(sequence, payload)| (sequence.wrapping_sub(base) as i32 as i64, payload.as_slice()))
+            .collect();
+        segments.sort_by_key(|s| s.0);
+        let start = segments.first().map(|s| s.0.min(0)).unwrap_or(0);
+
+        let mut data = Vec::new();
+        for (offset, payload) in segments {
+            let offset = (offset - start) as usize;
+            if offset > data.len() {
+                break;  // gap in the capture
+            }
+            let already_received = data.len() - offset;
+            if payload.len() > already_received {
+                data.extend(&payload[already_received..]);
+            }
+        }
+        data
+    }
+}
+
+/// Both directions of a tcp connection, `initiator` being the peer which sent the first packet
+pub struct Connection {
+    pub initiator: SocketAddr,
+    pub responder: SocketAddr,
+    pub initiator_data: Vec<u8>,
+    pub responder_data: Vec<u8>,
+}
+
+/// Group the segments by connection and reassemble both directions of each one.
+/// Connections are returned in the order of their first packet.
+pub fn reassemble(segments: Vec<TcpSegment<'_>>) -> Vec<Connection> {
+    struct Streams {
+        initiator: SocketAddr,
+        responder: SocketAddr,
+        forward: HalfStream,
+        backward: HalfStream,
+    }
+    let mut connections: Vec<Streams> = Vec::new();
+    let mut active: HashMap<(SocketAddr, SocketAddr), usize> = HashMap::new();
+
+    for segment in segments {
+        let key = if segment.source <= segment.destination {
+            (segment.source, segment.destination)
+        } else {
+            (segment.destination, segment.source)
+        };
+        let index = match active.get(&key) {
+            // A new syn on a connection already carrying data reuses the same ports for a new connection
+            Some(&index) if !(segment.syn && segment.source == connections[index].initiator
+                && !connections[index].forward.segments.is_empty()) => index,
+            _ => {
+                connections.push(Streams {
+                    initiator: segment.source,
+                    responder: segment.destination,
+                    forward: HalfStream::default(),
+                    backward: HalfStream::default(),
+                });
+                active.insert(key, connections.len() - 1);
+                connections.len() - 1
+            }
+        };
+
+        let streams = &mut connections[index];
+        let half = if segment.source == streams.initiator { &mut streams.forward } else { &mut streams.backward };
+        if segment.syn {
+            half.initial_sequence = Some(segment.sequence.wrapping_add(1));
+        } else if !segment.payload.is_empty() {
+            half.segments.push((segment.sequence, segment.payload.to_vec()));
+        }
+    }
+
+    connections.into_iter().map(|streams| Connection {
+        initiator: streams.initiator,
+        responder: streams.responder,
+        initiator_data: streams.forward.reassemble(),
+        responder_data: streams.backward.reassemble(),
+    }).collect()
+}
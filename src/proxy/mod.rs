@@ -0,0 +1,382 @@
+use std::fmt;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use crate::error::{DetailedError, JarmError};
+use crate::transport::{JarmStream, JarmTransport};
+#[cfg(feature = "tokio")]
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+#[cfg(feature = "tokio")]
+use crate::transport::AsyncJarmStream;
+
+const SOCKS_VERSION: u8 = 5;
+const SOCKS_NO_AUTHENTICATION: u8 = 0;
+const SOCKS_USERNAME_PASSWORD: u8 = 2;
+const SOCKS_NO_ACCEPTABLE_METHOD: u8 = 0xff;
+const SOCKS_CONNECT: u8 = 1;
+const SOCKS_IPV4: u8 = 1;
+const SOCKS_DOMAIN_NAME: u8 = 3;
+const SOCKS_IPV6: u8 = 4;
+/// Longest header accepted in the answer of an HTTP proxy
+const MAX_HTTP_HEADER_LENGTH: usize = 8192;
+
+/// Username and password given to the proxy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credentials {
+    pub username: String,
+    pub password: String,
+}
+
+/// Proxy every probe connection goes through
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Proxy {
+    /// SOCKS5 proxy, the host is resolved by the proxy
+    Socks5 { address: SocketAddr, credentials: Option<Credentials> },
+    /// HTTP proxy tunnelling the connection with the CONNECT method
+    HttpConnect { address: SocketAddr, credentials: Option<Credentials> },
+}
+
+impl Proxy {
+    pub fn address(&self) -> SocketAddr {
+        match self {
+            Proxy::Socks5 { address, .. } | Proxy::HttpConnect { address, .. } => *address,
+        }
+    }
+
+    fn credentials(&self) -> Option<&Credentials> {
+        match self {
+            Proxy::Socks5 { credentials, .. } | Proxy::HttpConnect { credentials, .. } => credentials.as_ref(),
+        }
+    }
+
+    /// Open a connection to the proxy and ask it for a tunnel to `host:port`, the whole handshake is done within `timeout`
+    pub(crate) fn connect(&self, transport: &dyn JarmTransport, host: &str, port: u16, timeout: Duration) -> Result<Box<dyn JarmStream>, JarmError> {
+        let mut stream = transport.connect(self.address(), timeout).map_err(proxy_error)?;
+        let mut handshake = HandshakeStream { stream: stream.as_mut(), deadline: Instant::now() + timeout };
+        match self {
+            Proxy::Socks5 { credentials, .. } => socks5_handshake(&mut handshake, credentials.as_ref(), host, port)?,
+            Proxy::HttpConnect { credentials, .. } => http_connect(&mut handshake, credentials.as_ref(), host, port)?,
+        }
+        Ok(stream)
+    }
+
+    /// Async counterpart of `connect`, the whole handshake is done within `timeout`
+    #[cfg(feature = "tokio")]
+    pub(crate) async fn connect_async(&self, transport: &dyn JarmTransport, host: &str, port: u16, timeout: Duration) -> Result<Box<dyn AsyncJarmStream>, JarmError> {
+        let mut stream = transport.connect_async(self.address(), timeout).await.map_err(proxy_error)?;
+        let handshake = async {
+            match self {
+                Proxy::Socks5 { credentials, .. } => socks5_handshake_async(stream.as_mut(), credentials.as_ref(), host, port).await,
+                Proxy::HttpConnect { credentials, .. } => http_connect_async(stream.as_mut(), credentials.as_ref(), host, port).await,
+            }
+        };
+        tokio::time::timeout(timeout, handshake).await
+            .map_err(|_| proxy_error(io::Error::new(ErrorKind::TimedOut, "proxy handshake timed out")))??;
+        Ok(stream)
+    }
+}
+
+impl fmt::Display for Proxy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scheme = match self {
+            Proxy::Socks5 { .. } => "socks5",
+            Proxy::HttpConnect { .. } => "http",
+        };
+        match self.credentials() {
+            Some(credentials) => write!(f, "{scheme}://{}:***@{}", credentials.username, self.address()),
+            None => write!(f, "{scheme}://{}", self.address()),
+        }
+    }
+}
+
+/// Parse `scheme://[username:password@]ip:port`, the scheme being `socks5` or `http`.
+/// Without scheme, the proxy is a SOCKS5 one as in jarm.py.
+impl FromStr for Proxy {
+    type Err = JarmError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (scheme, rest) = s.split_once("://").unwrap_or(("socks5", s));
+        let (credentials, address) = match rest.rsplit_once('@') {
+            Some((user_info, address)) => match user_info.split_once(':') {
+                Some((username, password)) => (Some(Credentials { username: username.to_string(), password: password.to_string() }), address),
+                None => return Err(proxy_error(format!("missing password in {s:?}"))),
+            },
+            None => (None, rest),
+        };
+        let address = address.trim_end_matches('/').parse().map_err(|_| proxy_error(format!("invalid proxy address in {s:?}")))?;
+        match scheme {
+            "socks5" | "socks5h" => Ok(Proxy::Socks5 { address, credentials }),
+            "http" => Ok(Proxy::HttpConnect { address, credentials }),
+            _ => Err(proxy_error(format!("unsupported proxy scheme {scheme:?}"))),
+        }
+    }
+}
+
+/// Stream to the proxy during the handshake, each read only waiting for the time left before `deadline`
+struct HandshakeStream<'a> {
+    stream: &'a mut dyn JarmStream,
+    deadline: Instant,
+}
+
+impl Read for HandshakeStream<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let timed_out = || io::Error::new(ErrorKind::TimedOut, "proxy handshake timed out");
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(timed_out());
+        }
+        self.stream.set_read_timeout(Some(remaining))?;
+        self.stream.read(buf).map_err(|e| match e.kind() {
+            ErrorKind::WouldBlock | ErrorKind::TimedOut => timed_out(),
+            _ => e,
+        })
+    }
+}
+
+impl Write for HandshakeStream<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+/// SOCKS5 negotiation and CONNECT request, see RFC 1928 and RFC 1929
+fn socks5_handshake(stream: &mut HandshakeStream, credentials: Option<&Credentials>, host: &str, port: u16) -> Result<(), JarmError> {
+    stream.write_all(&socks5_greeting(credentials)).map_err(proxy_error)?;
+    let mut choice = [0_u8; 2];
+    stream.read_exact(&mut choice).map_err(proxy_error)?;
+    if let Some(credentials) = socks5_method(choice, credentials)? {
+        stream.write_all(&socks5_authentication(credentials)?).map_err(proxy_error)?;
+        let mut status = [0_u8; 2];
+        stream.read_exact(&mut status).map_err(proxy_error)?;
+        check_socks5_authentication(status)?;
+    }
+
+    stream.write_all(&socks5_request(host, port)?).map_err(proxy_error)?;
+    let mut reply = [0_u8; 4];
+    stream.read_exact(&mut reply).map_err(proxy_error)?;
+    // Skip the address bound by the proxy, the TLS exchange starts right after
+    let bound_address_length = match socks5_bound_address_length(reply)? {
+        Some(length) => length,
+        None => {
+            let mut length = [0_u8; 1];
+            stream.read_exact(&mut length).map_err(proxy_error)?;
+            length[0] as usize
+        },
+    };
+    let mut bound_address = vec![0_u8; bound_address_length + 2];
+    stream.read_exact(&mut bound_address).map_err(proxy_error)
+}
+
+/// Async counterpart of `socks5_handshake`
+#[cfg(feature = "tokio")]
+async fn socks5_handshake_async(stream: &mut dyn AsyncJarmStream, credentials: Option<&Credentials>, host: &str, port: u16) -> Result<(), JarmError> {
+    stream.write_all(&socks5_greeting(credentials)).await.map_err(proxy_error)?;
+    let mut choice = [0_u8; 2];
+    stream.read_exact(&mut choice).await.map_err(proxy_error)?;
+    if let Some(credentials) = socks5_method(choice, credentials)? {
+        stream.write_all(&socks5_authentication(credentials)?).await.map_err(proxy_error)?;
+        let mut status = [0_u8; 2];
+        stream.read_exact(&mut status).await.map_err(proxy_error)?;
+        check_socks5_authentication(status)?;
+    }
+
+    stream.write_all(&socks5_request(host, port)?).await.map_err(proxy_error)?;
+    let mut reply = [0_u8; 4];
+    stream.read_exact(&mut reply).await.map_err(proxy_error)?;
+    let bound_address_length = match socks5_bound_address_length(reply)? {
+        Some(length) => length,
+        None => {
+            let mut length = [0_u8; 1];
+            stream.read_exact(&mut length).await.map_err(proxy_error)?;
+            length[0] as usize
+        },
+    };
+    let mut bound_address = vec![0_u8; bound_address_length + 2];
+    stream.read_exact(&mut bound_address).await.map(drop).map_err(proxy_error)
+}
+
+fn socks5_greeting(credentials: Option<&Credentials>) -> Vec<u8> {
+    let methods = match credentials {
+        Some(_) => vec![SOCKS_NO_AUTHENTICATION, SOCKS_USERNAME_PASSWORD],
+        None => vec![SOCKS_NO_AUTHENTICATION],
+    };
+    let mut greeting = vec![SOCKS_VERSION, methods.len() as u8];
+    greeting.extend(methods);
+    greeting
+}
+
+/// Check the method selected by the proxy, returning the credentials to authenticate with if it asks for them
+fn socks5_method(choice: [u8; 2], credentials: Option<&Credentials>) -> Result<Option<&Credentials>, JarmError> {
+    match (choice, credentials) {
+        ([SOCKS_VERSION, SOCKS_NO_AUTHENTICATION], _) => Ok(None),
+        ([SOCKS_VERSION, SOCKS_USERNAME_PASSWORD], Some(credentials)) => Ok(Some(credentials)),
+        ([SOCKS_VERSION, SOCKS_NO_ACCEPTABLE_METHOD], _) => Err(proxy_error("no acceptable authentication method")),
+        _ => Err(proxy_error("invalid SOCKS5 method selection")),
+    }
+}
+
+fn socks5_authentication(credentials: &Credentials) -> Result<Vec<u8>, JarmError> {
+    let username = u8::try_from(credentials.username.len()).map_err(|_| proxy_error("username too long for SOCKS5"))?;
+    let password = u8::try_from(credentials.password.len()).map_err(|_| proxy_error("password too long for SOCKS5"))?;
+    let mut request = vec![1, username];
+    request.extend(credentials.username.as_bytes());
+    request.push(password);
+    request.extend(credentials.password.as_bytes());
+    Ok(request)
+}
+
+fn check_socks5_authentication(status: [u8; 2]) -> Result<(), JarmError> {
+    match status[1] {
+        0 => Ok(()),
+        _ => Err(proxy_error("SOCKS5 authentication failed")),
+    }
+}
+
+fn socks5_request(host: &str, port: u16) -> Result<Vec<u8>, JarmError> {
+    let mut request = vec![SOCKS_VERSION, SOCKS_CONNECT, 0];
+    match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => {
+            request.push(SOCKS_IPV4);
+            request.extend(ip.octets());
+        },
+        Ok(IpAddr::V6(ip)) => {
+            request.push(SOCKS_IPV6);
+            request.extend(ip.octets());
+        },
+        Err(_) => {
+            let length = u8::try_from(host.len()).map_err(|_| proxy_error("host name too long for SOCKS5"))?;
+            request.extend([SOCKS_DOMAIN_NAME, length]);
+            request.extend(host.as_bytes());
+        },
+    }
+    request.extend(port.to_be_bytes());
+    Ok(request)
+}
+
+/// Length of the address bound by the proxy following the reply,
+/// `None` for a domain name whose length is given by the next byte
+fn socks5_bound_address_length(reply: [u8; 4]) -> Result<Option<usize>, JarmError> {
+    if reply[0] != SOCKS_VERSION {
+        return Err(proxy_error("invalid SOCKS5 reply"));
+    }
+    if reply[1] != 0 {
+        return Err(proxy_error(format!("SOCKS5 connection failed: {}", socks5_reply_message(reply[1]))));
+    }
+    match reply[3] {
+        SOCKS_IPV4 => Ok(Some(4)),
+        SOCKS_IPV6 => Ok(Some(16)),
+        SOCKS_DOMAIN_NAME => Ok(None),
+        _ => Err(proxy_error("invalid SOCKS5 address type")),
+    }
+}
+
+fn socks5_reply_message(reply: u8) -> &'static str {
+    match reply {
+        1 => "general SOCKS server failure",
+        2 => "connection not allowed by ruleset",
+        3 => "network unreachable",
+        4 => "host unreachable",
+        5 => "connection refused",
+        6 => "TTL expired",
+        7 => "command not supported",
+        8 => "address type not supported",
+        _ => "unknown error",
+    }
+}
+
+/// Ask an HTTP proxy for a tunnel, see RFC 9110 section 9.3.6
+fn http_connect(stream: &mut HandshakeStream, credentials: Option<&Credentials>, host: &str, port: u16) -> Result<(), JarmError> {
+    stream.write_all(http_connect_request(credentials, host, port).as_bytes()).map_err(proxy_error)?;
+    // Read byte by byte to leave the start of the TLS exchange in the stream
+    let mut header = Vec::new();
+    let mut byte = [0_u8; 1];
+    while !header.ends_with(b"\r\n\r\n") {
+        if header.len() >= MAX_HTTP_HEADER_LENGTH {
+            return Err(proxy_error("HTTP proxy answer too long"));
+        }
+        stream.read_exact(&mut byte).map_err(proxy_error)?;
+        header.push(byte[0]);
+    }
+    check_http_connect_answer(&header)
+}
+
+/// Async counterpart of `http_connect`
+#[cfg(feature = "tokio")]
+async fn http_connect_async(stream: &mut dyn AsyncJarmStream, credentials: Option<&Credentials>, host: &str, port: u16) -> Result<(), JarmError> {
+    stream.write_all(http_connect_request(credentials, host, port).as_bytes()).await.map_err(proxy_error)?;
+    let mut header = Vec::new();
+    let mut byte = [0_u8; 1];
+    while !header.ends_with(b"\r\n\r\n") {
+        if header.len() >= MAX_HTTP_HEADER_LENGTH {
+            return Err(proxy_error("HTTP proxy answer too long"));
+        }
+        stream.read_exact(&mut byte).await.map_err(proxy_error)?;
+        header.push(byte[0]);
+    }
+    check_http_connect_answer(&header)
+}
+
+fn http_connect_request(credentials: Option<&Credentials>, host: &str, port: u16) -> String {
+    let authority = match host.parse::<IpAddr>() {
+        Ok(IpAddr::V6(ip)) => format!("[{ip}]:{port}"),
+        _ => format!("{host}:{port}"),
+    };
+    let mut request = format!("CONNECT {authority} HTTP/1.1\r\nHost: {authority}\r\n");
+    if let Some(credentials) = credentials {
+        let token = base64_encode(format!("{}:{}", credentials.username, credentials.password).as_bytes());
+        request.push_str(&format!("Proxy-Authorization: Basic {token}\r\n"));
+    }
+    request.push_str("\r\n");
+    request
+}
+
+fn check_http_connect_answer(header: &[u8]) -> Result<(), JarmError> {
+    let header = String::from_utf8_lossy(header);
+    let status_line = header.lines().next().unwrap_or_default();
+    match status_line.split_whitespace().nth(1) {
+        Some(status) if status.starts_with('2') && status_line.starts_with("HTTP/") => Ok(()),
+        _ => Err(proxy_error(format!("HTTP proxy refused the tunnel: {status_line}"))),
+    }
+}
+
+fn base64_encode(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [chunk[0], chunk.get(1).copied().unwrap_or(0), chunk.get(2).copied().unwrap_or(0)];
+        let n = ((bytes[0] as u32) << 16) | ((bytes[1] as u32) << 8) | bytes[2] as u32;
+        for index in 0..4 {
+            if index <= chunk.len() {
+                encoded.push(ALPHABET[(n >> (18 - 6 * index)) as usize & 0x3f] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+    encoded
+}
+
+fn proxy_error<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> JarmError {
+    JarmError::Proxy(DetailedError::from(error.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use super::base64_encode;
+
+    #[rstest]
+    #[case(b"", "")]
+    #[case(b"f", "Zg==")]
+    #[case(b"fo", "Zm8=")]
+    #[case(b"foo", "Zm9v")]
+    #[case(b"user:pass", "dXNlcjpwYXNz")]
+    fn test_base64_encode(#[case] data: &[u8], #[case] expected: &str) {
+        assert_eq!(base64_encode(data), expected);
+    }
+}
@@ -0,0 +1,153 @@
+use std::fmt;
+use std::io::{self, ErrorKind};
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use crate::alert::TlsAlert;
+use crate::error::JarmError;
+use crate::response::{ResponseEnding, ServerResponse};
+use crate::{read_probe, FailurePolicy, Jarm, JarmPart, ProbeResult};
+
+/// How a probe ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProbeOutcome {
+    /// The server answered with a server hello
+    ServerHello,
+    /// The server answered with a TLS alert
+    Alert(TlsAlert),
+    /// The server answered with something else than a server hello or an alert
+    Other,
+    /// The server closed the connection without answering
+    Closed,
+    /// The connection or the answer didn't come before the timeout
+    Timeout,
+    /// The connection was refused or reset by the server
+    Reset,
+    /// Any other failure, e.g. the name can't be resolved or the answer can't be parsed
+    Failed,
+}
+
+impl fmt::Display for ProbeOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProbeOutcome::ServerHello => write!(f, "server hello"),
+            ProbeOutcome::Alert(alert) => write!(f, "{alert}"),
+            ProbeOutcome::Other => write!(f, "unexpected answer"),
+            ProbeOutcome::Closed => write!(f, "closed"),
+            ProbeOutcome::Timeout => write!(f, "timeout"),
+            ProbeOutcome::Reset => write!(f, "reset"),
+            ProbeOutcome::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+impl From<&io::Error> for ProbeOutcome {
+    fn from(error: &io::Error) -> Self {
+        match error.kind() {
+            ErrorKind::TimedOut | ErrorKind::WouldBlock => ProbeOutcome::Timeout,
+            ErrorKind::ConnectionRefused | ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted | ErrorKind::BrokenPipe => ProbeOutcome::Reset,
+            _ => ProbeOutcome::Failed,
+        }
+    }
+}
+
+/// Everything recorded while sending one probe
+#[derive(Debug)]
+pub struct ProbeReport {
+    /// Address the probe was sent to, the proxy if any. `None` if the name couldn't be resolved
+    pub address: Option<SocketAddr>,
+    /// Time to open the connection
+    pub connect_time: Option<Duration>,
+    /// Time between sending the client hello and receiving the first byte of the answer
+    pub time_to_first_byte: Option<Duration>,
+    pub bytes_received: usize,
+    pub outcome: ProbeOutcome,
+    pub part: JarmPart,
+    /// Error which made the probe fail, see `FailurePolicy` for how it affects the hash
+    pub error: Option<JarmError>,
+}
+
+impl ProbeReport {
+    /// Report of a probe which failed before getting an answer
+    pub(crate) fn failed(address: Option<SocketAddr>, connect_time: Option<Duration>, outcome: ProbeOutcome, error: JarmError) -> ProbeReport {
+        ProbeReport {
+            address,
+            connect_time,
+            time_to_first_byte: None,
+            bytes_received: 0,
+            outcome,
+            part: JarmPart::NoResponse,
+            error: Some(error),
+        }
+    }
+
+    /// Report of a probe from the answer read after sending the client hello at `sent`
+    pub(crate) fn answered(address: SocketAddr, connect_time: Duration, sent: Instant, response: ServerResponse) -> ProbeReport {
+        let time_to_first_byte = response.first_byte.map(|first_byte| first_byte.duration_since(sent));
+        let bytes_received = response.len();
+        let ending = response.ending;
+        let (part, alert, error) = match response.into_packet().and_then(read_probe) {
+            Ok(ProbeResult { part, alert }) => (part, alert, None),
+            Err(e) => (JarmPart::NoResponse, None, Some(e)),
+        };
+        let outcome = match (&part, alert) {
+            _ if error.is_some() => ProbeOutcome::Failed,
+            (_, Some(alert)) => ProbeOutcome::Alert(alert),
+            (JarmPart::ServerHello { .. }, _) => ProbeOutcome::ServerHello,
+            _ if bytes_received == 0 && ending == ResponseEnding::TimedOut => ProbeOutcome::Timeout,
+            _ if bytes_received == 0 => ProbeOutcome::Closed,
+            _ => ProbeOutcome::Other,
+        };
+        ProbeReport {
+            address: Some(address),
+            connect_time: Some(connect_time),
+            time_to_first_byte,
+            bytes_received,
+            outcome,
+            part,
+            error,
+        }
+    }
+
+    pub fn alert(&self) -> Option<TlsAlert> {
+        match self.outcome {
+            ProbeOutcome::Alert(alert) => Some(alert),
+            _ => None,
+        }
+    }
+}
+
+/// Report of a jarm scan, one entry per probe of the `Jarm` queue
+#[derive(Debug, Default)]
+pub struct JarmReport {
+    pub probes: Vec<ProbeReport>,
+    /// Address all the probes were sent to, the proxy if any. `None` if the host couldn't be resolved
+    pub address: Option<SocketAddr>,
+    /// Policy of the `Jarm` which sent the probes
+    pub failure_policy: FailurePolicy,
+}
+
+impl JarmReport {
+    /// Results of the probes.
+    /// With `FailurePolicy::Strict` this is the error of the first probe which failed, if any.
+    pub fn into_probes(self) -> Result<Vec<ProbeResult>, JarmError> {
+        let failure_policy = self.failure_policy;
+        self.probes.into_iter().map(|probe| {
+            let alert = probe.alert();
+            match (probe.error, failure_policy) {
+                (Some(error), FailurePolicy::Strict) => Err(error),
+                (Some(_), FailurePolicy::Reference) => Ok(ProbeResult { part: JarmPart::NoResponse, alert: None }),
+                (None, _) => Ok(ProbeResult { part: probe.part, alert }),
+            }
+        }).collect()
+    }
+
+    pub fn into_parts(self) -> Result<Vec<JarmPart>, JarmError> {
+        Ok(self.into_probes()?.into_iter().map(|probe| probe.part).collect())
+    }
+
+    /// Compute the jarm hash, same as `Jarm::hash` for the same server responses
+    pub fn hash(self) -> Result<String, JarmError> {
+        let jarm = Jarm { parts: self.into_parts()?, ..Jarm::default() };
+        jarm.compute_hash()
+    }
+}
@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
+use crate::error::{DetailedError, JarmError};
+
+/// Name resolution used to find the addresses to probe
+pub trait JarmResolver: Send + Sync {
+    /// Every address of `host`, with the given port
+    fn resolve(&self, host: &str, port: u16) -> Result<Vec<SocketAddr>, JarmError>;
+}
+
+/// Resolution through the system resolver, as done by `ToSocketAddrs`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemResolver;
+
+impl JarmResolver for SystemResolver {
+    fn resolve(&self, host: &str, port: u16) -> Result<Vec<SocketAddr>, JarmError> {
+        match (host, port).to_socket_addrs() {
+            Ok(addresses) => Ok(addresses.collect()),
+            Err(e) => Err(dns_error(host, DetailedError::from(Box::from(e)))),
+        }
+    }
+}
+
+/// Resolution from a fixed map of hosts, e.g. a hosts file or a cache filled beforehand.
+/// IP literals are resolved as is, any other unknown host is an error.
+#[derive(Debug, Clone, Default)]
+pub struct StaticResolver {
+    pub hosts: HashMap<String, Vec<IpAddr>>,
+}
+
+impl StaticResolver {
+    pub fn new() -> StaticResolver {
+        StaticResolver::default()
+    }
+
+    /// Add an address to the ones of `host`
+    pub fn with_host(mut self, host: &str, ip: IpAddr) -> StaticResolver {
+        self.hosts.entry(host.to_string()).or_default().push(ip);
+        self
+    }
+}
+
+impl JarmResolver for StaticResolver {
+    fn resolve(&self, host: &str, port: u16) -> Result<Vec<SocketAddr>, JarmError> {
+        match (self.hosts.get(host), host.parse::<IpAddr>()) {
+            (Some(ips), _) => Ok(ips.iter().map(|ip| SocketAddr::new(*ip, port)).collect()),
+            (None, Ok(ip)) => Ok(vec![SocketAddr::new(ip, port)]),
+            (None, Err(_)) => Err(dns_error(host, DetailedError::from(Box::from("unknown host")))),
+        }
+    }
+}
+
+pub(crate) fn dns_error(name: &str, error: DetailedError) -> JarmError {
+    JarmError::DnsResolve { name: name.to_string(), error }
+}
@@ -0,0 +1,239 @@
+use std::io::{self, ErrorKind};
+use std::time::{Duration, Instant};
+use crate::alert::CONTENT_TYPE_ALERT;
+use crate::error::JarmError;
+use crate::{parse_error, SOCKET_BUFFER};
+use crate::transport::JarmStream;
+#[cfg(feature = "tokio")]
+use tokio::io::AsyncReadExt;
+#[cfg(feature = "tokio")]
+use crate::transport::AsyncJarmStream;
+
+const RECORD_HEADER_LENGTH: usize = 5;
+const HANDSHAKE_HEADER_LENGTH: usize = 4;
+const CONTENT_TYPE_HANDSHAKE: u8 = 22;
+const HANDSHAKE_SERVER_HELLO: u8 = 2;
+/// Most data read from a server, a TLS record of the maximum length and its header, see RFC 8446 section 5.1
+const MAX_RESPONSE_LENGTH: usize = (1 << 14) + RECORD_HEADER_LENGTH;
+
+/// Why the reading of the server response stopped
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseEnding {
+    #[default]
+    Complete,
+    Closed,
+    TimedOut,
+    /// `MAX_RESPONSE_LENGTH` was read without the response being complete
+    TooLong,
+}
+
+/// Whether handshake messages hold a server hello
+#[derive(PartialEq, Eq)]
+enum ServerHelloState {
+    Complete,
+    /// The server hello, or a message it may follow, is still being received
+    Pending,
+    /// Every message is complete and none is a server hello
+    Missing,
+}
+
+/// Bytes received from the server, until the server hello (or an alert) is complete
+#[derive(Default)]
+pub struct ServerResponse {
+    data: Vec<u8>,
+    pub first_byte: Option<Instant>,
+    pub ending: ResponseEnding,
+}
+
+impl From<Vec<u8>> for ServerResponse {
+    fn from(data: Vec<u8>) -> Self {
+        ServerResponse { data, ..ServerResponse::default() }
+    }
+}
+
+/// A TLS record found in the response
+struct Record<'a> {
+    content_type: u8,
+    fragment: &'a [u8],
+}
+
+impl ServerResponse {
+    pub fn extend(&mut self, bytes: &[u8]) {
+        if self.first_byte.is_none() && !bytes.is_empty() {
+            self.first_byte = Some(Instant::now());
+        }
+        self.data.extend(bytes);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    /// Complete records at the start of the response, stopping at the first non handshake record
+    fn leading_records(&self) -> Vec<Record<'_>> {
+        let mut records = Vec::new();
+        let mut offset = 0;
+        while let Some(header) = self.data.get(offset..offset + RECORD_HEADER_LENGTH) {
+            let length = ((header[3] as usize) << 8) + header[4] as usize;
+            let Some(fragment) = self.data.get(offset + RECORD_HEADER_LENGTH..offset + RECORD_HEADER_LENGTH + length) else {
+                break;
+            };
+            records.push(Record { content_type: header[0], fragment });
+            if header[0] != CONTENT_TYPE_HANDSHAKE {
+                break;
+            }
+            offset += RECORD_HEADER_LENGTH + length;
+        }
+        records
+    }
+
+    /// Whether enough data was received to compute the jarm part.
+    /// That is a complete server hello, possibly spanning several records, or a complete alert record.
+    /// A response which isn't a TLS record, or whose first handshake record holds no server hello,
+    /// can't get any better and is considered complete.
+    pub fn is_complete(&self) -> bool {
+        match self.data.first() {
+            None => false,
+            Some(&CONTENT_TYPE_HANDSHAKE) | Some(&CONTENT_TYPE_ALERT) => {
+                let mut handshake = Vec::new();
+                for (index, record) in self.leading_records().into_iter().enumerate() {
+                    if record.content_type != CONTENT_TYPE_HANDSHAKE {
+                        return true;  // alert or unexpected record, nothing more to wait for
+                    }
+                    handshake.extend(record.fragment);
+                    match server_hello_state(&handshake) {
+                        ServerHelloState::Complete => return true,
+                        ServerHelloState::Missing if index == 0 => return true,
+                        _ => {},
+                    }
+                }
+                false
+            }
+            Some(_) => true,
+        }
+    }
+
+    /// Data to give to `read_packet`.
+    /// A server hello split over several records is merged back into a single record, and the
+    /// data is padded with zeros to the size of the buffer used when reading a single segment.
+    /// A TLS record cut short by a timeout or the connection closing is a `Parse` error, padding it would make up its end.
+    pub fn into_packet(self) -> Result<Vec<u8>, JarmError> {
+        let tls_record = matches!(self.data.first(), Some(&CONTENT_TYPE_HANDSHAKE) | Some(&CONTENT_TYPE_ALERT));
+        if tls_record && self.ending != ResponseEnding::Complete && !self.is_complete() {
+            let reason = match self.ending {
+                ResponseEnding::TooLong => "server hello longer than a TLS record",
+                _ => "incomplete TLS record",
+            };
+            return Err(parse_error(self.data.len(), reason));
+        }
+
+        let records = self.leading_records();
+        let first_record_complete = records.first()
+            .is_none_or(|r| r.content_type != CONTENT_TYPE_HANDSHAKE || server_hello_state(r.fragment) == ServerHelloState::Complete);
+
+        let mut packet = if first_record_complete {
+            self.data
+        } else {
+            let handshake_records: Vec<&Record> = records.iter()
+                .take_while(|r| r.content_type == CONTENT_TYPE_HANDSHAKE)
+                .collect();
+            let handshake: Vec<u8> = handshake_records.iter().flat_map(|r| r.fragment.iter().copied()).collect();
+            let consumed: usize = handshake_records.iter().map(|r| RECORD_HEADER_LENGTH + r.fragment.len()).sum();
+
+            let mut packet = self.data[..3].to_vec();  // content type and version of the first record
+            packet.extend((handshake.len().min(u16::MAX as usize) as u16).to_be_bytes());
+            packet.extend(handshake);
+            packet.extend(&self.data[consumed..]);
+            packet
+        };
+        if packet.len() < SOCKET_BUFFER as usize {
+            packet.resize(SOCKET_BUFFER as usize, 0);
+        }
+        Ok(packet)
+    }
+}
+
+fn server_hello_state(handshake: &[u8]) -> ServerHelloState {
+    let mut offset = 0;
+    while let Some(header) = handshake.get(offset..offset + HANDSHAKE_HEADER_LENGTH) {
+        let length = ((header[1] as usize) << 16) + ((header[2] as usize) << 8) + header[3] as usize;
+        let end = offset + HANDSHAKE_HEADER_LENGTH + length;
+        if end > handshake.len() {
+            return ServerHelloState::Pending;
+        }
+        if header[0] == HANDSHAKE_SERVER_HELLO {
+            return ServerHelloState::Complete;
+        }
+        offset = end;
+    }
+    match offset == handshake.len() {
+        true => ServerHelloState::Missing,
+        false => ServerHelloState::Pending,  // partial message header
+    }
+}
+
+/// Read the server response until it is complete, the connection is closed or the timeout is reached
+pub fn read_response(stream: &mut dyn JarmStream, timeout: Duration) -> io::Result<ServerResponse> {
+    let deadline = Instant::now() + timeout;
+    let mut response = ServerResponse::default();
+    let mut buffer = [0_u8; SOCKET_BUFFER as usize];
+    while !response.is_complete() {
+        if response.len() >= MAX_RESPONSE_LENGTH {
+            response.ending = ResponseEnding::TooLong;
+            break;
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            response.ending = ResponseEnding::TimedOut;
+            break;
+        }
+        stream.set_read_timeout(Some(remaining))?;
+        match stream.read(&mut buffer) {
+            Ok(0) => {
+                response.ending = ResponseEnding::Closed;
+                break;
+            },
+            Ok(read) => response.extend(&buffer[..read]),
+            // Keep what was received before the timeout
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) && !response.is_empty() => {
+                response.ending = ResponseEnding::TimedOut;
+                break;
+            },
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(response)
+}
+
+/// Async counterpart of `read_response`
+#[cfg(feature = "tokio")]
+pub async fn read_response_async(stream: &mut dyn AsyncJarmStream, timeout: Duration) -> io::Result<ServerResponse> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut response = ServerResponse::default();
+    let mut buffer = [0_u8; SOCKET_BUFFER as usize];
+    while !response.is_complete() {
+        if response.len() >= MAX_RESPONSE_LENGTH {
+            response.ending = ResponseEnding::TooLong;
+            break;
+        }
+        match tokio::time::timeout_at(deadline, stream.read(&mut buffer)).await {
+            Ok(Ok(0)) => {
+                response.ending = ResponseEnding::Closed;
+                break;
+            },
+            Ok(Ok(read)) => response.extend(&buffer[..read]),
+            Ok(Err(e)) => return Err(e),
+            // Keep what was received before the timeout
+            Err(_) if !response.is_empty() => {
+                response.ending = ResponseEnding::TimedOut;
+                break;
+            },
+            Err(_) => return Err(io::Error::from(ErrorKind::TimedOut)),
+        }
+    }
+    Ok(response)
+}
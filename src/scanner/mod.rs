@@ -0,0 +1,144 @@
+use std::sync::mpsc::{channel, sync_channel, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::error::JarmError;
+use crate::resolver::{JarmResolver, SystemResolver};
+use crate::{FailurePolicy, Jarm, DEFAULT_TIMEOUT};
+
+const DEFAULT_WORKERS: usize = 16;
+
+/// Host and port of a scanned target, as given to `Jarm::new`
+pub type ScanTarget = (String, String);
+
+impl From<ScanTarget> for Jarm {
+    fn from((host, port): ScanTarget) -> Jarm {
+        Jarm::new(host, port)
+    }
+}
+
+/// Compute the jarm hash of many targets in parallel.
+/// Each target goes through the regular `Jarm` probe queue, scans run in a pool of `workers` threads.
+pub struct Scanner {
+    /// Maximum number of targets scanned at the same time
+    pub workers: usize,
+    /// Maximum duration of the scan of each target, covering the connections of all its probes.
+    /// See `Jarm::scan_timeout`, a single connection may take up to the whole of it.
+    pub timeout: Duration,
+    /// Maximum number of target scans started per second, across all workers
+    pub rate_limit: Option<u32>,
+    /// Policy applied to the failed probes of each target, see `Jarm::failure_policy`
+    pub failure_policy: FailurePolicy,
+    /// Resolver shared by all the scans, e.g. a cache in front of the system resolver
+    pub resolver: Arc<dyn JarmResolver>,
+}
+
+impl Default for Scanner {
+    fn default() -> Self {
+        Scanner {
+            workers: DEFAULT_WORKERS,
+            timeout: DEFAULT_TIMEOUT,
+            rate_limit: None,
+            failure_policy: FailurePolicy::default(),
+            resolver: Arc::new(SystemResolver),
+        }
+    }
+}
+
+impl Scanner {
+    pub fn new(workers: usize) -> Scanner {
+        Scanner { workers, ..Scanner::default() }
+    }
+
+    /// Start scanning the targets in background, each one being turned into its `Jarm`.
+    /// Targets are e.g. a `Target` from `TargetExpander` or the importers, or a `ScanTarget`.
+    /// The results are yielded in completion order, not in the targets order.
+    pub fn scan<T, I>(&self, targets: I) -> ScanResults<T>
+    where
+        T: Clone + Into<Jarm> + Send + 'static,
+        I: IntoIterator<Item = T>,
+        I::IntoIter: Send + 'static,
+    {
+        let receiver = self.run(targets, |target: &T| target.clone().into());
+        ScanResults { receiver }
+    }
+
+    /// Hash every job in the pool of workers, `new_jarm` giving the `Jarm` of each job.
+    /// The scanner settings are applied to every `Jarm`, results are sent in completion order.
+    pub(crate) fn run<T, I, F>(&self, jobs: I, new_jarm: F) -> Receiver<(T, Result<String, JarmError>)>
+    where
+        T: Send + 'static,
+        I: IntoIterator<Item = T>,
+        I::IntoIter: Send + 'static,
+        F: Fn(&T) -> Jarm + Send + Sync + 'static,
+    {
+        let workers = self.workers.max(1);
+        let (job_sender, job_receiver) = sync_channel::<T>(workers);
+        let (result_sender, result_receiver) = channel();
+
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+        let new_jarm = Arc::new(new_jarm);
+        for _ in 0..workers {
+            let job_receiver = Arc::clone(&job_receiver);
+            let result_sender = result_sender.clone();
+            let new_jarm = Arc::clone(&new_jarm);
+            let timeout = self.timeout;
+            let failure_policy = self.failure_policy;
+            let resolver = Arc::clone(&self.resolver);
+            thread::spawn(move || {
+                loop {
+                    // The lock is released as soon as a job is received
+                    let next_job = job_receiver.lock().unwrap().recv();
+                    let Ok(job) = next_job else { break };  // no more jobs
+
+                    let mut jarm = new_jarm(&job);
+                    jarm.timeout = timeout;
+                    jarm.scan_timeout = Some(timeout);
+                    jarm.failure_policy = failure_policy;
+                    jarm.resolver = Arc::clone(&resolver);
+                    let result = jarm.hash();
+                    if result_sender.send((job, result)).is_err() {
+                        break;  // results are not consumed anymore
+                    }
+                }
+            });
+        }
+
+        let jobs = jobs.into_iter();
+        let rate_limit = self.rate_limit;
+        thread::spawn(move || feed_jobs(jobs, job_sender, rate_limit));
+
+        result_receiver
+    }
+}
+
+/// Send the jobs to the workers, waiting between each one to respect the rate limit
+fn feed_jobs<T, I: Iterator<Item = T>>(jobs: I, sender: SyncSender<T>, rate_limit: Option<u32>) {
+    let interval = rate_limit.filter(|rate| *rate > 0).map(|rate| Duration::from_secs(1) / rate);
+    let mut next_start = Instant::now();
+    for job in jobs {
+        if let Some(interval) = interval {
+            let now = Instant::now();
+            if next_start > now {
+                thread::sleep(next_start - now);
+            }
+            next_start = Instant::now().max(next_start) + interval;
+        }
+        if sender.send(job).is_err() {
+            return;  // all workers stopped
+        }
+    }
+}
+
+/// Iterator over the scan results, yielded as soon as each target scan completes
+pub struct ScanResults<T = ScanTarget> {
+    receiver: Receiver<(T, Result<String, JarmError>)>,
+}
+
+impl<T> Iterator for ScanResults<T> {
+    type Item = (T, Result<String, JarmError>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receiver.recv().ok()
+    }
+}
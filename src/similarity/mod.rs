@@ -0,0 +1,83 @@
+use crate::hash::{JarmHash, PROBE_COUNT};
+use crate::JarmPart;
+
+/// Share of the score given to each probe, split evenly between its cipher and its version
+pub const PROBE_WEIGHT: f64 = 0.08;
+/// Share of the score given to the ALPNs and extensions, the ten probes weighing the remaining 0.8
+pub const EXTENSIONS_WEIGHT: f64 = 0.2;
+
+/// Agreement of two scans on a probe, two probes without response agreeing on everything
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProbeComparison {
+    pub cipher: bool,
+    pub version: bool,
+    /// Same ALPN and extensions, only known when comparing parts as a hash keeps a digest of every probe
+    pub extensions: Option<bool>,
+}
+
+impl ProbeComparison {
+    pub fn is_match(&self) -> bool {
+        self.cipher && self.version && self.extensions != Some(false)
+    }
+}
+
+/// Comparison of two scans, probe by probe
+#[derive(Debug, Clone, PartialEq)]
+pub struct Similarity {
+    pub probes: Vec<ProbeComparison>,
+    /// Same ALPN and extensions digest, which for parts means every probe got the same ALPN and extensions
+    pub extensions_digest: bool,
+}
+
+impl Similarity {
+    /// Number of probes on which both scans fully agree
+    pub fn matching_probes(&self) -> usize {
+        self.probes.iter().filter(|probe| probe.is_match()).count()
+    }
+
+    /// Similarity between 0 and 1, 1 for identical scans.
+    /// Each probe adds `PROBE_WEIGHT / 2` for the same cipher and as much for the same version.
+    /// `EXTENSIONS_WEIGHT` is added when the digests match, or shared between the probes getting the same
+    /// ALPN and extensions when comparing parts.
+    pub fn score(&self) -> f64 {
+        let agreements = self.probes.iter().map(|probe| usize::from(probe.cipher) + usize::from(probe.version)).sum::<usize>();
+        let probes = PROBE_WEIGHT / 2.0 * agreements as f64;
+        let extensions = match self.probes.iter().map(|probe| probe.extensions).collect::<Option<Vec<bool>>>() {
+            Some(extensions) => EXTENSIONS_WEIGHT * extensions.iter().filter(|same| **same).count() as f64 / PROBE_COUNT as f64,
+            None if self.extensions_digest => EXTENSIONS_WEIGHT,
+            None => 0.0,
+        };
+        probes + extensions
+    }
+}
+
+/// Compare two hashes on the cipher and version of each probe, and on the extensions digest
+pub fn compare_hashes(a: &JarmHash, b: &JarmHash) -> Similarity {
+    let probes = a.probes().iter().zip(b.probes())
+        .map(|(a, b)| ProbeComparison { cipher: a.cipher_index == b.cipher_index, version: a.version == b.version, extensions: None })
+        .collect();
+    Similarity { probes, extensions_digest: a.extensions_digest() == b.extensions_digest() }
+}
+
+/// Compare the parts of two scans, including the ALPN and extensions of each probe.
+/// Missing parts count as probes without response.
+pub fn compare_parts(a: &[JarmPart], b: &[JarmPart]) -> Similarity {
+    let probes: Vec<ProbeComparison> = (0..PROBE_COUNT).map(|index| match (part(a, index), part(b, index)) {
+        (JarmPart::NoResponse, JarmPart::NoResponse) => ProbeComparison { cipher: true, version: true, extensions: Some(true) },
+        (
+            JarmPart::ServerHello { cipher, version, alpn, extensions },
+            JarmPart::ServerHello { cipher: other_cipher, version: other_version, alpn: other_alpn, extensions: other_extensions },
+        ) => ProbeComparison {
+            cipher: cipher == other_cipher,
+            version: version == other_version,
+            extensions: Some(alpn == other_alpn && extensions == other_extensions),
+        },
+        _ => ProbeComparison { cipher: false, version: false, extensions: Some(false) },
+    }).collect();
+    let extensions_digest = probes.iter().all(|probe| probe.extensions == Some(true));
+    Similarity { probes, extensions_digest }
+}
+
+fn part(parts: &[JarmPart], index: usize) -> &JarmPart {
+    parts.get(index).unwrap_or(&JarmPart::NoResponse)
+}
@@ -0,0 +1,155 @@
+use std::net::IpAddr;
+use crate::error::JarmError;
+use crate::target::{invalid_target, validate_host_name};
+
+const BASE: u32 = 36;
+const T_MIN: u32 = 1;
+const T_MAX: u32 = 26;
+const SKEW: u32 = 38;
+const DAMP: u32 = 700;
+const INITIAL_BIAS: u32 = 72;
+const INITIAL_N: u32 = 128;
+const ACE_PREFIX: &str = "xn--";
+/// Full stops separating the labels of an internationalized domain name, see RFC 3490
+const LABEL_SEPARATORS: [char; 4] = ['.', '\u{3002}', '\u{ff0e}', '\u{ff61}'];
+
+/// Server name sent in the SNI extension of the client hellos
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub enum ServerName {
+    /// The host of the probe, even if it is an IP literal, as done by the reference implementation
+    #[default]
+    Host,
+    /// The host of the probe, without SNI if it is an IP literal as required by RFC 6066
+    HostName,
+    /// A chosen virtual host, whatever the host connected to
+    Name(String),
+    /// No SNI extension at all
+    Omitted,
+}
+
+impl ServerName {
+    /// Check a chosen name is a host name which fits in the SNI extension, as the host of a `Target`
+    pub fn validate(&self) -> Result<(), JarmError> {
+        match self {
+            ServerName::Name(name) => validate_host_name(name).map(drop).map_err(|reason| invalid_target(name, reason)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Name to send in the SNI extension for `host`, in its ASCII form.
+    /// A name which can't be encoded to ASCII is left out as well.
+    pub fn for_host(&self, host: &str) -> Option<String> {
+        match self {
+            ServerName::Host => domain_to_ascii(host).ok(),
+            ServerName::HostName if host.parse::<IpAddr>().is_ok() => None,
+            ServerName::HostName => domain_to_ascii(host).ok(),
+            ServerName::Name(name) => domain_to_ascii(name).ok(),
+            ServerName::Omitted => None,
+        }
+    }
+}
+
+/// ASCII form of an internationalized domain name, each non ASCII label being lowercased and punycode encoded.
+/// ASCII names are kept as is. A label too long to be punycode encoded is an `InvalidTarget` error.
+pub fn domain_to_ascii(domain: &str) -> Result<String, JarmError> {
+    if domain.is_ascii() {
+        return Ok(domain.to_string());
+    }
+    let labels = domain.split(LABEL_SEPARATORS)
+        .map(|label| {
+            let label = label.to_lowercase();
+            match label.is_ascii() {
+                true => Some(label),
+                false => punycode(&label).map(|encoded| format!("{ACE_PREFIX}{encoded}")),
+            }
+        })
+        .collect::<Option<Vec<_>>>();
+    match labels {
+        Some(labels) => Ok(labels.join(".")),
+        None => Err(JarmError::InvalidTarget { target: domain.to_string(), reason: "label too long to be punycode encoded" }),
+    }
+}
+
+/// Punycode encoding of a label, see RFC 3492.
+/// `None` on overflow, as required by the encoding procedure of section 6.3. That only happens with very long labels.
+fn punycode(label: &str) -> Option<String> {
+    let code_points: Vec<u32> = label.chars().map(u32::from).collect();
+    let mut output: String = label.chars().filter(char::is_ascii).collect();
+    let basic_count = u32::try_from(output.len()).ok()?;
+    if basic_count > 0 {
+        output.push('-');
+    }
+
+    let (mut n, mut delta, mut bias) = (INITIAL_N, 0_u32, INITIAL_BIAS);
+    let mut handled = basic_count;
+    while (handled as usize) < code_points.len() {
+        let next = code_points.iter().copied().filter(|&c| c >= n).min().unwrap_or(n);
+        delta = (next - n).checked_mul(handled + 1).and_then(|skipped| delta.checked_add(skipped))?;
+        n = next;
+        for &c in &code_points {
+            if c < n {
+                delta = delta.checked_add(1)?;
+            }
+            if c == n {
+                let mut q = delta;
+                let mut k = BASE;
+                loop {
+                    let t = if k <= bias { T_MIN } else if k >= bias + T_MAX { T_MAX } else { k - bias };
+                    if q < t {
+                        break;
+                    }
+                    output.push(encode_digit(t + (q - t) % (BASE - t)));
+                    q = (q - t) / (BASE - t);
+                    k += BASE;
+                }
+                output.push(encode_digit(q));
+                bias = adapt(delta, handled + 1, handled == basic_count);
+                delta = 0;
+                handled += 1;
+            }
+        }
+        delta = delta.checked_add(1)?;
+        n += 1;
+    }
+    Some(output)
+}
+
+fn adapt(delta: u32, points: u32, first_time: bool) -> u32 {
+    let mut delta = if first_time { delta / DAMP } else { delta / 2 };
+    delta += delta / points;
+    let mut k = 0;
+    while delta > ((BASE - T_MIN) * T_MAX) / 2 {
+        delta /= BASE - T_MIN;
+        k += BASE;
+    }
+    k + (BASE - T_MIN + 1) * delta / (delta + SKEW)
+}
+
+fn encode_digit(digit: u32) -> char {
+    match digit {
+        0..=25 => (b'a' + digit as u8) as char,
+        _ => (b'0' + (digit - 26) as u8) as char,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use super::punycode;
+
+    // Samples of RFC 3492 section 7.1
+    #[rstest]
+    #[case("ليهمابتكلموشعربي؟", "egbpdaj6bu4bxfgehfvwxn")]
+    #[case("他们为什么不说中文", "ihqwcrb4cv8a8dqg056pqjye")]
+    #[case("3年b組金八先生", "3b-ww4c5e180e575a65lsy2b")]
+    #[case("münchen", "mnchen-3ya")]
+    fn test_punycode(#[case] label: &str, #[case] expected: &str) {
+        assert_eq!(punycode(label).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_punycode_overflow() {
+        let label = format!("{}\u{10ffff}", "a".repeat(4000));
+        assert_eq!(punycode(&label), None);
+    }
+}
@@ -0,0 +1,167 @@
+use std::fmt;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr};
+use std::str::FromStr;
+use crate::error::JarmError;
+use crate::sni::{domain_to_ascii, ServerName};
+use crate::{Jarm, PacketSpecification};
+
+/// Port of a target given without one, as for an https URL
+pub const DEFAULT_PORT: u16 = 443;
+/// Longest host name, see RFC 1035. It also bounds the name sent in the SNI extension.
+const MAX_HOST_NAME_LENGTH: usize = 253;
+const MAX_LABEL_LENGTH: usize = 63;
+
+/// Host of a target, either a domain name or an IP literal
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TargetHost {
+    Name(String),
+    Ip(IpAddr),
+}
+
+impl fmt::Display for TargetHost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TargetHost::Name(name) => write!(f, "{name}"),
+            TargetHost::Ip(ip) => write!(f, "{ip}"),
+        }
+    }
+}
+
+/// Validated server to scan
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Target {
+    pub host: TargetHost,
+    pub port: u16,
+    /// Name sent in the SNI extension, the host by default
+    pub server_name: ServerName,
+    /// Address every probe is sent to instead of resolving the host, see `Jarm::address`
+    pub address: Option<SocketAddr>,
+}
+
+impl Target {
+    /// Target from a domain name or an IP literal, without brackets
+    pub fn new(host: &str, port: u16) -> Result<Target, JarmError> {
+        let invalid = |reason| invalid_target(&format!("{host}:{port}"), reason);
+        if port == 0 {
+            return Err(invalid("port 0 can't be scanned"));
+        }
+        let host = match host.parse::<IpAddr>() {
+            Ok(ip) => TargetHost::Ip(ip),
+            Err(_) => TargetHost::Name(validate_host_name(host).map_err(invalid)?.to_string()),
+        };
+        Ok(Target { host, port, server_name: ServerName::default(), address: None })
+    }
+
+    /// Name to send in the SNI extension, an `InvalidTarget` error if it isn't a valid host name
+    pub fn with_server_name(mut self, server_name: ServerName) -> Result<Target, JarmError> {
+        server_name.validate()?;
+        self.server_name = server_name;
+        Ok(self)
+    }
+
+    pub fn with_address(mut self, address: SocketAddr) -> Target {
+        self.address = Some(address);
+        self
+    }
+
+    /// The ten probes of a jarm scan of this target
+    pub fn probe_queue(&self) -> Vec<PacketSpecification> {
+        Jarm::from(self.clone()).queue
+    }
+}
+
+impl From<Target> for Jarm {
+    fn from(target: Target) -> Jarm {
+        let mut jarm = Jarm::new(target.host.to_string(), target.port.to_string());
+        for spec in &mut jarm.queue {
+            spec.server_name = target.server_name.clone();
+        }
+        jarm.address = target.address;
+        jarm
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.host {
+            TargetHost::Ip(IpAddr::V6(ip)) => write!(f, "[{ip}]:{}", self.port),
+            _ => write!(f, "{}:{}", self.host, self.port),
+        }
+    }
+}
+
+/// Parse `host`, `host:port`, `[ipv6]:port` or an `https://host:port/path` URL.
+/// The port is `DEFAULT_PORT` when omitted.
+impl FromStr for Target {
+    type Err = JarmError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason| invalid_target(s, reason);
+        let authority = match s.split_once("://") {
+            Some((scheme, rest)) if scheme.eq_ignore_ascii_case("https") => rest.split(['/', '?', '#']).next().unwrap_or_default(),
+            Some(_) => return Err(invalid("only https URLs can be scanned")),
+            None => s,
+        };
+        if authority.contains('@') {
+            return Err(invalid("user information is not supported"));
+        }
+        if let Ok(ip) = authority.parse::<Ipv6Addr>() {
+            return Target::new(&ip.to_string(), DEFAULT_PORT);  // IPv6 without brackets nor port
+        }
+
+        let (host, port) = match authority.strip_prefix('[') {
+            Some(bracketed) => {
+                let (ip, port) = bracketed.split_once(']').ok_or_else(|| invalid("missing closing bracket"))?;
+                if ip.parse::<Ipv6Addr>().is_err() {
+                    return Err(invalid("invalid IPv6 address"));
+                }
+                match port {
+                    "" => (ip, None),
+                    _ => (ip, Some(port.strip_prefix(':').ok_or_else(|| invalid("unexpected characters after the IPv6 address"))?)),
+                }
+            },
+            None => match authority.rsplit_once(':') {
+                Some((host, port)) => (host, Some(port)),
+                None => (authority, None),
+            },
+        };
+        let port = match port {
+            None => DEFAULT_PORT,
+            Some("") => return Err(invalid("missing port after ':'")),
+            Some(port) => port.parse().map_err(|_| invalid("the port is not a number between 1 and 65535"))?,
+        };
+        Target::new(host, port).map_err(|e| match e {
+            JarmError::InvalidTarget { reason, .. } => invalid(reason),
+            e => e,
+        })
+    }
+}
+
+/// Check the host name fits in DNS queries and in the SNI extension, once encoded to ASCII
+pub(crate) fn validate_host_name(host: &str) -> Result<&str, &'static str> {
+    // A label only overflows the punycode encoder far beyond the length limit
+    let ascii = domain_to_ascii(host).map_err(|_| "label of host name longer than 63 bytes")?;
+    let name = ascii.strip_suffix('.').unwrap_or(&ascii);  // fully qualified name
+    if name.is_empty() {
+        return Err("empty host");
+    }
+    if name.len() > MAX_HOST_NAME_LENGTH {
+        return Err("host name longer than 253 bytes");
+    }
+    for label in name.split('.') {
+        if label.is_empty() {
+            return Err("empty label in host name");
+        }
+        if label.len() > MAX_LABEL_LENGTH {
+            return Err("label of host name longer than 63 bytes");
+        }
+        if !label.bytes().all(|byte| byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_') {
+            return Err("invalid character in host name");
+        }
+    }
+    Ok(host)
+}
+
+pub(crate) fn invalid_target(target: &str, reason: &'static str) -> JarmError {
+    JarmError::InvalidTarget { target: target.to_string(), reason }
+}
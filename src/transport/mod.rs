@@ -0,0 +1,101 @@
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::Duration;
+#[cfg(feature = "tokio")]
+use std::future::Future;
+#[cfg(feature = "tokio")]
+use std::pin::Pin;
+#[cfg(feature = "tokio")]
+use std::task::{Context, Poll};
+#[cfg(feature = "tokio")]
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// Byte stream a probe is sent through
+pub trait JarmStream: Read + Write + Send {
+    /// Timeout of the next reads, as `TcpStream::set_read_timeout`.
+    /// Streams which never block can ignore it.
+    fn set_read_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()>;
+}
+
+impl JarmStream for TcpStream {
+    fn set_read_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        TcpStream::set_read_timeout(self, timeout)
+    }
+}
+
+/// Byte stream a probe is sent through by the async scans
+#[cfg(feature = "tokio")]
+pub trait AsyncJarmStream: AsyncRead + AsyncWrite + Send + Unpin {}
+
+#[cfg(feature = "tokio")]
+impl<S: AsyncRead + AsyncWrite + Send + Unpin> AsyncJarmStream for S {}
+
+/// Connection opened by `JarmTransport::connect_async`
+#[cfg(feature = "tokio")]
+pub type AsyncConnect<'a> = Pin<Box<dyn Future<Output = io::Result<Box<dyn AsyncJarmStream>>> + Send + 'a>>;
+
+/// Way to open the connection of each probe
+pub trait JarmTransport: Send + Sync {
+    /// Open a new connection to `address` for one probe, within `timeout`
+    fn connect(&self, address: SocketAddr, timeout: Duration) -> io::Result<Box<dyn JarmStream>>;
+
+    /// Async counterpart of `connect`, used by the async scans.
+    /// By default the stream of `connect` is read and written in place, which blocks the task:
+    /// that only suits streams which never block, other transports should override it.
+    #[cfg(feature = "tokio")]
+    fn connect_async(&self, address: SocketAddr, timeout: Duration) -> AsyncConnect<'_> {
+        Box::pin(async move {
+            let stream = self.connect(address, timeout)?;
+            Ok(Box::new(BlockingStream(stream)) as Box<dyn AsyncJarmStream>)
+        })
+    }
+}
+
+/// Plain TCP connection to the address, the default transport
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TcpTransport;
+
+impl JarmTransport for TcpTransport {
+    fn connect(&self, address: SocketAddr, timeout: Duration) -> io::Result<Box<dyn JarmStream>> {
+        let stream = TcpStream::connect_timeout(&address, timeout)?;
+        stream.set_write_timeout(Some(timeout))?;
+        Ok(Box::new(stream))
+    }
+
+    #[cfg(feature = "tokio")]
+    fn connect_async(&self, address: SocketAddr, timeout: Duration) -> AsyncConnect<'_> {
+        Box::pin(async move {
+            let stream = tokio::time::timeout(timeout, tokio::net::TcpStream::connect(address)).await
+                .map_err(|_| io::Error::from(io::ErrorKind::TimedOut))??;
+            Ok(Box::new(stream) as Box<dyn AsyncJarmStream>)
+        })
+    }
+}
+
+/// Stream of a blocking transport used by the async scans, see `JarmTransport::connect_async`
+#[cfg(feature = "tokio")]
+struct BlockingStream(Box<dyn JarmStream>);
+
+#[cfg(feature = "tokio")]
+impl AsyncRead for BlockingStream {
+    fn poll_read(mut self: Pin<&mut Self>, _: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let read = self.0.read(buf.initialize_unfilled())?;
+        buf.advance(read);
+        Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl AsyncWrite for BlockingStream {
+    fn poll_write(mut self: Pin<&mut Self>, _: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Poll::Ready(self.0.write(buf))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(self.0.flush())
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
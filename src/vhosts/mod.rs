@@ -0,0 +1,65 @@
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use crate::error::JarmError;
+use crate::scanner::Scanner;
+use crate::sni::ServerName;
+use crate::Jarm;
+
+/// Jarm hash of one address for each server name sent
+#[derive(Debug, Default)]
+pub struct ServerNameHashes {
+    /// Hash of each server name, in the order they were given
+    pub hashes: Vec<(ServerName, Result<String, JarmError>)>,
+}
+
+impl ServerNameHashes {
+    /// Server names grouped by the hash they produced, the failed scans are left out
+    pub fn groups(&self) -> BTreeMap<&str, Vec<&ServerName>> {
+        let mut groups: BTreeMap<&str, Vec<&ServerName>> = BTreeMap::new();
+        for (server_name, hash) in &self.hashes {
+            if let Ok(hash) = hash {
+                groups.entry(hash).or_default().push(server_name);
+            }
+        }
+        groups
+    }
+
+    /// Server names producing a hash no other server name produced
+    pub fn unique(&self) -> Vec<&ServerName> {
+        let groups = self.groups();
+        self.hashes.iter()
+            .filter(|(_, hash)| hash.as_ref().is_ok_and(|hash| groups[hash.as_str()].len() == 1))
+            .map(|(server_name, _)| server_name)
+            .collect()
+    }
+}
+
+impl Scanner {
+    /// Scan one address once per server name, `ServerName::Omitted` being the scan without SNI.
+    /// Each scan runs the full probe queue, they share the workers and the rate limit of the scanner.
+    /// A server name which isn't a valid host name isn't scanned and gets its `InvalidTarget` error.
+    pub fn scan_server_names(&self, address: SocketAddr, server_names: Vec<ServerName>) -> ServerNameHashes {
+        let mut hashes = Vec::new();
+        let mut jobs = Vec::new();
+        for (index, server_name) in server_names.into_iter().enumerate() {
+            match server_name.validate() {
+                Ok(()) => jobs.push((index, server_name)),
+                Err(e) => hashes.push(((index, server_name), Err(e))),
+            }
+        }
+        let results = self.run(jobs, move |(_, server_name): &(usize, ServerName)| {
+            let mut jarm = Jarm::new(address.ip().to_string(), address.port().to_string());
+            jarm.address = Some(address);
+            for spec in &mut jarm.queue {
+                spec.server_name = server_name.clone();  // already validated
+            }
+            jarm
+        });
+
+        hashes.extend(results);
+        hashes.sort_by_key(|((index, _), _)| *index);
+        ServerNameHashes {
+            hashes: hashes.into_iter().map(|((_, server_name), hash)| (server_name, hash)).collect(),
+        }
+    }
+}
@@ -0,0 +1,133 @@
+#![allow(dead_code)]  // each test crate only uses part of the helpers
+
+use std::io::{self, Cursor, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use rust_jarm::resolver::StaticResolver;
+use rust_jarm::transport::{JarmStream, JarmTransport};
+use rust_jarm::{Jarm, TestRng};
+
+/// Server hello (followed by the start of the certificate) sent back by a cloudflare server
+pub const SERVER_HELLO_HEX: &str = "160303004c0200004803035ffb8b2d1d50e207efcff257647b8cb319bd10a920b6968d444f574e4752440100c02b0000200000000000170000ff01000100000b000201000023000000100005000302683216030308a50b0008a100089e0004c7308204c330820469a003020102021003f93e0cd51ed9e174d552a522425dba300a06082a8648ce3d040302304a310b300906035504061302555331193017060355040a1310436c6f7564666c6172652c20496e632e3120301e06035504031317436c6f7564666c61726520496e63204543432043412d33301e170d3230303732393030303030305a170d3231303732393132303030305a306d310b3009060355040613025553310b3009060355040813024341311630140603550407130d53616e204672616e636973636f31193017060355040a1310436c6f7564666c6172652c20496e632e311e301c06035504031315736e692e636c6f7564666c61726573736c2e636f6d3059301306072a8648ce3d020106082a8648ce3d03010703420004d73c51db4658abcb9d7ab52ff121496eb4c7e8e985d8742b20cef649c6e4ad1a692c44a12964c289bc2bd4aa22d767a0e7f95802de915a05e0ede1b4b9ce9636a382030c30820308301f0603551d23041830168014a5ce37eaebb0750e946788b445fad9241087961f301d0603551d0e0416041455da5417da45572aac6f8b2988693e361b204b75303e0603551d1104373035820e2a2e74797069636f64652e636f6d8215736e692e636c6f7564666c61726573736c2e636f6d820c74797069636f64652e636f6d300e0603551d0f0101ff040403020780301d0603551d250416301406082b0601050507030106082b06010505070302307b0603551d1f047430723037a035a0338631687474703a2f2f63726c332e64696769636572742e636f6d2f436c6f7564666c617265496e6345434343412d332e63726c3037a035a0338631687474703a2f2f63726c342e64696769636572742e636f6d2f436c6f7564666c617265496e6345434343412d332e63726c304c0603551d2004453043303706096086480186fd6c0101302a302806082b06010505070201161c68747470733a2f2f7777772e64696769636572742e636f6d2f4350533008060667810c010202307606082b06010505070101046a3068302406082b060105050730018618687474703a2f2f6f6373702e64696769636572742e636f6d304006082b060105050730028634687474703a2f2f636163657274732e64696769636572742e636f6d2f436c6f7564666c617265496e6345434343412d332e637274";
+
+/// Jarm hash obtained when every probe is answered with `SERVER_HELLO_HEX`
+pub const SERVER_HELLO_HASH: &str = "27d27d27d27d27d27d27d27d27d27debd865e63a4441da99411bab3aadfedf";
+
+pub fn server_hello() -> Vec<u8> {
+    hex::decode(SERVER_HELLO_HEX).unwrap()
+}
+
+/// Jarm of the local server listening on `port`, with deterministic client hellos and a short timeout
+pub fn local_jarm(port: u16) -> Jarm {
+    let mut jarm = Jarm::new("127.0.0.1".to_string(), port.to_string());
+    jarm.rng = Box::new(TestRng {});
+    jarm.timeout = Duration::from_secs(2);
+    jarm
+}
+
+/// Jarm of `jarm.example`, resolved to `ips` on the given port
+pub fn resolved_jarm(port: u16, ips: &[IpAddr]) -> Jarm {
+    let mut jarm = Jarm::new("jarm.example".to_string(), port.to_string());
+    jarm.rng = Box::new(TestRng {});
+    jarm.resolver = Arc::new(ips.iter().fold(StaticResolver::new(), |resolver, ip| resolver.with_host("jarm.example", *ip)));
+    jarm
+}
+
+/// Local port nothing listens on
+pub fn closed_port() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap().port()  // the listener is dropped, nothing listens anymore
+}
+
+/// Read a full client hello record from the stream
+pub fn read_client_hello(stream: &mut TcpStream) -> Vec<u8> {
+    let mut header = [0_u8; 5];
+    stream.read_exact(&mut header).unwrap();
+    let length = ((header[3] as usize) << 8) + header[4] as usize;
+    let mut body = vec![0_u8; length];
+    stream.read_exact(&mut body).unwrap();
+    let mut client_hello = header.to_vec();
+    client_hello.extend(body);
+    client_hello
+}
+
+/// Spawn a local TLS stand-in server answering every client hello with `response`
+pub fn spawn_tls_server(response: Vec<u8>) -> SocketAddr {
+    spawn_server(move |mut stream| {
+        read_client_hello(&mut stream);
+        stream.write_all(&response).unwrap();
+    })
+}
+
+/// Spawn a local server running `handler` on every incoming connection, each in its own thread
+pub fn spawn_server<F>(handler: F) -> SocketAddr
+where
+    F: Fn(TcpStream) + Send + Sync + Clone + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = stream.unwrap();
+            let handler = handler.clone();
+            thread::spawn(move || handler(stream));
+        }
+    });
+    address
+}
+
+/// Client hellos received by a `CannedTransport`, with the address they were sent to
+pub type ClientHellos = Arc<Mutex<Vec<(SocketAddr, Vec<u8>)>>>;
+
+/// Transport answering every probe with `response` without any socket, recording the client hellos
+pub struct CannedTransport {
+    pub response: Vec<u8>,
+    pub client_hellos: ClientHellos,
+}
+
+impl CannedTransport {
+    pub fn new(response: Vec<u8>) -> CannedTransport {
+        CannedTransport { response, client_hellos: Arc::default() }
+    }
+}
+
+impl JarmTransport for CannedTransport {
+    fn connect(&self, address: SocketAddr, _timeout: Duration) -> io::Result<Box<dyn JarmStream>> {
+        Ok(Box::new(CannedStream {
+            address,
+            response: Cursor::new(self.response.clone()),
+            client_hellos: Arc::clone(&self.client_hellos),
+        }))
+    }
+}
+
+struct CannedStream {
+    address: SocketAddr,
+    response: Cursor<Vec<u8>>,
+    client_hellos: ClientHellos,
+}
+
+impl Read for CannedStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.response.read(buf)
+    }
+}
+
+impl Write for CannedStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.client_hellos.lock().unwrap().push((self.address, buf.to_vec()));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl JarmStream for CannedStream {
+    fn set_read_timeout(&mut self, _timeout: Option<Duration>) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::net::{IpAddr, SocketAddr, TcpListener};
+    use std::thread;
+    use rust_jarm::addresses::Consistency;
+    use rust_jarm::error::JarmError;
+    use rust_jarm::{Jarm, TestRng};
+    use crate::common::{read_client_hello, resolved_jarm, server_hello, spawn_tls_server, SERVER_HELLO_HASH};
+
+    const ALERT: [u8; 7] = [0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 0x28];
+
+    /// Listen on the same port of 127.0.0.1 and 127.0.0.2, answering the client hellos with `responses`
+    fn spawn_servers(responses: [Vec<u8>; 2]) -> [SocketAddr; 2] {
+        let first = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = first.local_addr().unwrap().port();
+        let second = TcpListener::bind(("127.0.0.2", port)).unwrap();
+        for (listener, response) in [first, second].into_iter().zip(responses) {
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let mut stream = stream.unwrap();
+                    read_client_hello(&mut stream);
+                    let _ = stream.write_all(&response);
+                }
+            });
+        }
+        [SocketAddr::from(([127, 0, 0, 1], port)), SocketAddr::from(([127, 0, 0, 2], port))]
+    }
+
+    #[test]
+    fn test_hash_all_addresses_single_address() {
+        let address = spawn_tls_server(server_hello());
+        let mut jarm = Jarm::new("127.0.0.1".to_string(), address.port().to_string());
+        jarm.rng = Box::new(TestRng {});
+
+        let result = jarm.hash_all_addresses().unwrap();
+
+        assert_eq!(result.hashes.len(), 1);
+        assert_eq!(result.hashes[&address].as_ref().unwrap(), SERVER_HELLO_HASH);
+        assert_eq!(result.consistency(), Consistency::Consistent);
+    }
+
+    #[test]
+    fn test_hash_all_addresses_consistent() {
+        let addresses = spawn_servers([server_hello(), server_hello()]);
+        let jarm = resolved_jarm(addresses[0].port(), &addresses.map(|a| a.ip()));
+
+        let result = jarm.hash_all_addresses().unwrap();
+
+        assert_eq!(result.hashes.len(), 2);
+        assert!(result.hashes.values().all(|hash| hash.as_ref().unwrap() == SERVER_HELLO_HASH));
+        assert_eq!(result.consistency(), Consistency::Consistent);
+    }
+
+    #[test]
+    fn test_hash_all_addresses_inconsistent() {
+        let addresses = spawn_servers([server_hello(), ALERT.to_vec()]);
+        let jarm = resolved_jarm(addresses[0].port(), &addresses.map(|a| a.ip()));
+
+        let result = jarm.hash_all_addresses().unwrap();
+
+        assert_eq!(result.hashes[&addresses[0]].as_ref().unwrap(), SERVER_HELLO_HASH);
+        assert_eq!(result.hashes[&addresses[1]].as_ref().unwrap(), &"0".repeat(62));
+        assert_eq!(result.consistency(), Consistency::Inconsistent);
+    }
+
+    #[test]
+    fn test_hash_all_addresses_incomplete() {
+        let address = spawn_tls_server(server_hello());
+        let closed: IpAddr = [127, 0, 0, 3].into();  // nothing listens on it
+        let jarm = resolved_jarm(address.port(), &[address.ip(), closed]);
+
+        let result = jarm.hash_all_addresses().unwrap();
+
+        assert!(result.hashes[&SocketAddr::new(closed, address.port())].is_err());
+        assert_eq!(result.consistency(), Consistency::Incomplete);
+    }
+
+    #[test]
+    fn test_hash_all_addresses_dns_error() {
+        let jarm = resolved_jarm(443, &[]);
+
+        let error = jarm.hash_all_addresses().err().unwrap();
+
+        assert!(matches!(error, JarmError::DnsResolve { name, .. } if name == "jarm.example"));
+    }
+}
@@ -0,0 +1,78 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use rstest::rstest;
+    use rust_jarm::alert::{read_alert, TlsAlert};
+    use rust_jarm::{read_probe, JarmPart};
+    use crate::common::{local_jarm, read_client_hello, server_hello, spawn_server};
+
+    const HANDSHAKE_FAILURE: [u8; 7] = [0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 0x28];
+
+    #[rstest]
+    #[case(2, 40, "fatal alert handshake_failure (40)")]
+    #[case(2, 70, "fatal alert protocol_version (70)")]
+    #[case(1, 112, "warning alert unrecognized_name (112)")]
+    #[case(3, 255, "unknown alert unknown (255)")]
+    fn test_alert_display(#[case] level: u8, #[case] description: u8, #[case] expected: &str) {
+        assert_eq!(TlsAlert { level, description }.to_string(), expected);
+    }
+
+    #[test]
+    fn test_read_alert() {
+        let alert = read_alert(&HANDSHAKE_FAILURE).unwrap();
+
+        assert_eq!(alert, TlsAlert { level: 2, description: 40 });
+        assert!(alert.is_fatal());
+        assert_eq!(alert.description_name(), "handshake_failure");
+    }
+
+    #[rstest]
+    #[case(vec![])]
+    #[case(vec![0x15, 0x03, 0x03, 0x00, 0x02, 0x02])]
+    #[case(vec![0x15, 0x03, 0x03, 0x00, 0x01, 0x02, 0x28])]
+    #[case(server_hello())]
+    fn test_read_alert_none(#[case] data: Vec<u8>) {
+        assert_eq!(read_alert(&data), None);
+    }
+
+    #[test]
+    fn test_read_probe() {
+        let alert_probe = read_probe(HANDSHAKE_FAILURE.to_vec()).unwrap();
+        let server_hello_probe = read_probe(server_hello()).unwrap();
+
+        assert_eq!(alert_probe.part, JarmPart::NoResponse);
+        assert_eq!(alert_probe.alert, Some(TlsAlert { level: 2, description: 40 }));
+        assert!(matches!(server_hello_probe.part, JarmPart::ServerHello { .. }));
+        assert_eq!(server_hello_probe.alert, None);
+    }
+
+    #[test]
+    fn test_retrieve_probes_reports_rejected_probes() {
+        // Only the TLS 1.3 probes get a server hello
+        let address = spawn_server(|mut stream| {
+            let client_hello = read_client_hello(&mut stream);
+            let response = match client_hello[9..11] {
+                [0x03, 0x03] if client_hello.windows(2).any(|w| w == [0x00, 0x2b]) => server_hello(),
+                _ => HANDSHAKE_FAILURE.to_vec(),
+            };
+            stream.write_all(&response).unwrap();
+        });
+        let mut jarm = local_jarm(address.port());
+
+        let probes = jarm.retrieve_probes().unwrap();
+        let parts = local_jarm(address.port()).retrieve_parts().unwrap();
+
+        assert_eq!(probes.len(), 10);
+        assert_eq!(probes.iter().map(|p| p.part.clone()).collect::<Vec<_>>(), parts);
+        for probe in &probes {
+            match probe.part {
+                JarmPart::NoResponse => assert_eq!(probe.alert, Some(TlsAlert { level: 2, description: 40 })),
+                _ => assert_eq!(probe.alert, None),
+            }
+        }
+        assert!(probes.iter().any(|p| p.alert.is_some()));
+        assert!(probes.iter().any(|p| p.alert.is_none()));
+    }
+}
@@ -0,0 +1,110 @@
+#![cfg(feature = "tokio")]
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+    use rust_jarm::error::JarmError;
+    use crate::common::{local_jarm, server_hello, spawn_server, spawn_tls_server, CannedTransport, SERVER_HELLO_HASH};
+
+    #[tokio::test]
+    async fn test_hash_async() {
+        let address = spawn_tls_server(server_hello());
+
+        let hash = local_jarm(address.port()).hash_async().await.unwrap();
+
+        assert_eq!(hash, SERVER_HELLO_HASH);
+    }
+
+    #[tokio::test]
+    async fn test_hash_async_matches_hash() {
+        let address = spawn_tls_server(server_hello());
+
+        let async_hash = local_jarm(address.port()).hash_async().await.unwrap();
+        let sync_hash = tokio::task::spawn_blocking(move || local_jarm(address.port()).hash())
+            .await.unwrap().unwrap();
+
+        assert_eq!(async_hash, sync_hash);
+    }
+
+    #[tokio::test]
+    async fn test_retrieve_parts_async_keeps_probe_order() {
+        let address = spawn_tls_server(server_hello());
+        let jarm = local_jarm(address.port());
+
+        let parts = jarm.retrieve_parts_async().await.unwrap();
+
+        assert_eq!(parts.len(), jarm.queue.len());
+        assert!(parts.iter().all(|p| p.to_string() == "c02b|0303|h2|0000-0017-ff01-000b-0023-0010"));
+    }
+
+    #[tokio::test]
+    async fn test_retrieve_parts_async_is_concurrent() {
+        // Each connection is answered after a delay, sequential probes would need 10 times it
+        let delay = Duration::from_millis(500);
+        let address = spawn_server(move |mut stream| {
+            crate::common::read_client_hello(&mut stream);
+            std::thread::sleep(delay);
+            std::io::Write::write_all(&mut stream, &server_hello()).unwrap();
+        });
+        let jarm = local_jarm(address.port());
+
+        let start = std::time::Instant::now();
+        jarm.retrieve_parts_async().await.unwrap();
+
+        assert!(start.elapsed() < delay * 5);
+    }
+
+    #[tokio::test]
+    async fn test_hash_async_response_sent_byte_by_byte() {
+        let address = spawn_server(|mut stream| {
+            stream.set_nodelay(true).unwrap();
+            crate::common::read_client_hello(&mut stream);
+            for byte in &server_hello()[..81] {  // server hello record only
+                std::io::Write::write_all(&mut stream, &[*byte]).unwrap();
+                std::thread::sleep(Duration::from_millis(5));
+            }
+            let _ = std::io::Read::read(&mut stream, &mut [0_u8; 1]);  // keep the connection open
+        });
+
+        let hash = local_jarm(address.port()).hash_async().await.unwrap();
+
+        assert_eq!(hash, SERVER_HELLO_HASH);
+    }
+
+    #[tokio::test]
+    async fn test_report_async() {
+        let address = spawn_tls_server(server_hello());
+
+        let report = local_jarm(address.port()).report_async().await;
+
+        assert_eq!(report.probes.len(), 10);
+        assert!(report.probes.iter().all(|p| p.outcome == rust_jarm::report::ProbeOutcome::ServerHello));
+        assert_eq!(report.hash().unwrap(), SERVER_HELLO_HASH);
+    }
+
+    #[tokio::test]
+    async fn test_hash_async_connection_error() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);  // nothing is listening on this port anymore
+
+        let error = local_jarm(port).hash_async().await.err().unwrap();
+
+        assert!(matches!(error, JarmError::Connection(_)));
+    }
+
+    #[tokio::test]
+    async fn test_hash_async_over_transport() {
+        let transport = Arc::new(CannedTransport::new(server_hello()));
+        let mut jarm = local_jarm(443);
+        jarm.transport = transport.clone();
+
+        let hash = jarm.hash_async().await.unwrap();
+
+        assert_eq!(hash, SERVER_HELLO_HASH);
+        assert_eq!(transport.client_hellos.lock().unwrap().len(), 10);
+    }
+}
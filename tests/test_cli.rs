@@ -0,0 +1,102 @@
+#![cfg(feature = "cli")]
+
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use std::process::{Command, Output};
+    use crate::common::{server_hello, spawn_tls_server, SERVER_HELLO_HASH};
+
+    fn jarm(args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_jarm")).args(args).output().unwrap()
+    }
+
+    fn temp_file(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("rust_jarm_{}_{name}", std::process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn test_cli_scan() {
+        let address = spawn_tls_server(server_hello());
+
+        let output = jarm(&["127.0.0.1", "-p", &address.port().to_string(), "-t", "2"]);
+
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert_eq!(stdout, format!("Domain: 127.0.0.1\nResolved IP: 127.0.0.1\nJARM: {SERVER_HELLO_HASH}\n"));
+    }
+
+    #[test]
+    fn test_cli_verbose() {
+        let address = spawn_tls_server(server_hello());
+
+        let output = jarm(&["127.0.0.1", "-v", "-p", &address.port().to_string()]);
+
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert!(stdout.contains("Scan 1: c02b|0303|h2|0000-0017-ff01-000b-0023-0010\n"));
+        assert!(stdout.contains("Scan 10: c02b|0303|h2|0000-0017-ff01-000b-0023-0010\n"));
+    }
+
+    #[test]
+    fn test_cli_verbose_alert() {
+        let address = spawn_tls_server(vec![0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 0x46]);
+
+        let output = jarm(&["127.0.0.1", "-v", "-p", &address.port().to_string()]);
+
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        assert!(stdout.contains("Scan 1: ||| (fatal alert protocol_version (70))\n"));
+        assert!(stdout.contains(&format!("JARM: {}\n", "0".repeat(62))));
+    }
+
+    #[test]
+    fn test_cli_input_and_csv_output() {
+        let address = spawn_tls_server(server_hello());
+        let input = temp_file("input.txt");
+        let output = temp_file("output.csv");
+        fs::write(&input, format!("127.0.0.1,{}\n\nlocalhost,{}\n", address.port(), address.port())).unwrap();
+
+        let result = jarm(&["-i", input.to_str().unwrap(), "-o", output.to_str().unwrap()]);
+
+        assert!(result.status.success());
+        let csv = fs::read_to_string(&output).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], format!("127.0.0.1,127.0.0.1,{SERVER_HELLO_HASH}"));
+        assert!(lines[1].starts_with("localhost,"));
+    }
+
+    #[test]
+    fn test_cli_requires_a_target() {
+        let output = jarm(&[]);
+
+        assert!(!output.status.success());
+    }
+
+    #[test]
+    fn test_cli_scan_conflicts_with_input() {
+        let output = jarm(&["127.0.0.1", "-i", "input.txt"]);
+
+        assert!(!output.status.success());
+        assert!(String::from_utf8(output.stderr).unwrap().contains("cannot be used with"));
+    }
+
+    #[test]
+    fn test_cli_invalid_server_name() {
+        let output = jarm(&["127.0.0.1", "--sni", "foo bar"]);
+
+        assert!(!output.status.success());
+        assert!(String::from_utf8(output.stderr).unwrap().contains("invalid character in host name"));
+    }
+
+    #[test]
+    fn test_cli_invalid_proxy() {
+        let output = jarm(&["127.0.0.1", "--proxy", "ftp://127.0.0.1:21"]);
+
+        assert!(!output.status.success());
+        assert!(String::from_utf8(output.stderr).unwrap().contains("unsupported proxy scheme"));
+    }
+}
@@ -0,0 +1,118 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use rstest::*;
+    use rust_jarm::error::JarmError;
+    use rust_jarm::expand::TargetExpander;
+    use rust_jarm::target::Target;
+
+    fn expand(inputs: &[&str]) -> Vec<String> {
+        expand_with(TargetExpander::new(), inputs)
+    }
+
+    fn expand_with(expander: TargetExpander, inputs: &[&str]) -> Vec<String> {
+        let inputs: Vec<String> = inputs.iter().map(|input| input.to_string()).collect();
+        expander.expand(inputs).map(|target| target.unwrap().to_string()).collect()
+    }
+
+    #[rstest]
+    #[case("example.com", vec!["example.com:443"])]
+    #[case("example.com:443,8443", vec!["example.com:443", "example.com:8443"])]
+    #[case("1.2.3.4:8000-8002", vec!["1.2.3.4:8000", "1.2.3.4:8001", "1.2.3.4:8002"])]
+    #[case("1.2.3.4:8443,443-444,443", vec!["1.2.3.4:443", "1.2.3.4:444", "1.2.3.4:8443"])]
+    #[case("10.0.0.0/30", vec!["10.0.0.0:443", "10.0.0.1:443", "10.0.0.2:443", "10.0.0.3:443"])]
+    #[case("10.0.0.5/31:443,853", vec!["10.0.0.4:443", "10.0.0.4:853", "10.0.0.5:443", "10.0.0.5:853"])]
+    #[case("2001:db8::/127", vec!["[2001:db8::]:443", "[2001:db8::1]:443"])]
+    #[case("[2001:db8::1]:8443", vec!["[2001:db8::1]:8443"])]
+    #[case("[2001:db8::/127]:853", vec!["[2001:db8::]:853", "[2001:db8::1]:853"])]
+    #[case("255.255.255.255/32:65535", vec!["255.255.255.255:65535"])]
+    fn test_expand_pattern(#[case] pattern: &str, #[case] expected: Vec<&str>) {
+        assert_eq!(expand(&[pattern]), expected);
+    }
+
+    #[test]
+    fn test_expand_several_patterns_per_input() {
+        let targets = expand(&["a.example b.example:8443  # comment", "", "# only a comment", "1.2.3.4"]);
+
+        assert_eq!(targets, vec!["a.example:443", "b.example:8443", "1.2.3.4:443"]);
+    }
+
+    #[test]
+    fn test_expand_deduplicates() {
+        let targets = expand(&["10.0.0.0/31", "10.0.0.1", "10.0.0.0/30", "A.example:443", "a.example:443,8443"]);
+
+        assert_eq!(targets, vec!["10.0.0.0:443", "10.0.0.1:443", "10.0.0.2:443", "10.0.0.3:443", "A.example:443", "a.example:8443"]);
+    }
+
+    #[test]
+    fn test_expand_exclusions() {
+        let expander = TargetExpander::new()
+            .exclude("10.0.0.0/31").unwrap()
+            .exclude("10.0.0.3:8443").unwrap()
+            .exclude("skip.example").unwrap();
+
+        let targets = expand_with(expander, &["10.0.0.0/30:443,8443", "skip.example:443", "keep.example"]);
+
+        assert_eq!(targets, vec!["10.0.0.2:443", "10.0.0.2:8443", "10.0.0.3:443", "keep.example:443"]);
+    }
+
+    #[test]
+    fn test_expand_default_port() {
+        let mut expander = TargetExpander::new();
+        expander.default_port = 8443;
+
+        assert_eq!(expand_with(expander, &["example.com", "example.org:443"]), vec!["example.com:8443", "example.org:443"]);
+    }
+
+    #[test]
+    fn test_expand_is_lazy() {
+        // A /8 of IPv6 addresses can't be materialised
+        let mut targets = TargetExpander::new().expand(["[2001:db8::/8]:1-65535"]);
+
+        assert_eq!(targets.next().unwrap().unwrap().to_string(), "[2000::]:1");
+        assert_eq!(targets.next().unwrap().unwrap().to_string(), "[2000::]:2");
+    }
+
+    #[rstest]
+    #[case("10.0.0.0/33", "network prefix length too long")]
+    #[case("10.0.0.0/abc", "invalid network prefix length")]
+    #[case("host.example/24", "invalid network address")]
+    #[case("1.2.3.4:8010-8000", "port range ending before its start")]
+    #[case("1.2.3.4:0", "the port is not a number between 1 and 65535")]
+    #[case("1.2.3.4:https", "the port is not a number between 1 and 65535")]
+    #[case("[2001:db8::1", "missing closing bracket")]
+    #[case("bad..example", "empty label in host name")]
+    fn test_expand_invalid_pattern(#[case] pattern: &str, #[case] expected_reason: &str) {
+        let mut targets = TargetExpander::new().expand([pattern, "valid.example"]);
+
+        match targets.next().unwrap() {
+            Err(JarmError::InvalidTarget { target, reason }) => {
+                assert_eq!(target, pattern);
+                assert_eq!(reason, expected_reason);
+            },
+            other => panic!("unexpected result {other:?}"),
+        }
+        // The expansion goes on after an invalid pattern
+        assert_eq!(targets.next().unwrap().unwrap().to_string(), "valid.example:443");
+        assert!(targets.next().is_none());
+    }
+
+    #[test]
+    fn test_expand_file() {
+        let path = std::env::temp_dir().join(format!("rust_jarm_{}_targets.txt", std::process::id()));
+        fs::write(&path, "10.0.0.0/31\nexample.com:443,8443\n").unwrap();
+
+        let targets: Vec<Target> = TargetExpander::new().expand_file(&path).unwrap().map(Result::unwrap).collect();
+        fs::remove_file(&path).unwrap();
+
+        let targets: Vec<String> = targets.iter().map(Target::to_string).collect();
+        assert_eq!(targets, vec!["10.0.0.0:443", "10.0.0.1:443", "example.com:443", "example.com:8443"]);
+    }
+
+    #[test]
+    fn test_expand_missing_file() {
+        assert!(matches!(TargetExpander::new().expand_file("/non_existing_path"), Err(JarmError::Io(_))));
+    }
+}
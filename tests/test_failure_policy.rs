@@ -0,0 +1,86 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::net::SocketAddr;
+    use std::str::FromStr;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use rust_jarm::error::JarmError;
+    use rust_jarm::scanner::Scanner;
+    use rust_jarm::{FailurePolicy, Jarm, JarmPart};
+    use crate::common::{closed_port, local_jarm, read_client_hello, server_hello, spawn_server};
+
+    const DROPPED_PROBES: [usize; 2] = [2, 7];
+
+    fn jarm_with_policy(port: u16, failure_policy: FailurePolicy) -> Jarm {
+        let mut jarm = local_jarm(port);
+        jarm.failure_policy = failure_policy;
+        jarm
+    }
+
+    /// Server resetting the connection of the `DROPPED_PROBES`, in the order of the `Jarm` queue
+    fn spawn_dropping_server() -> SocketAddr {
+        let connections = Arc::new(AtomicUsize::new(0));
+        spawn_server(move |mut stream| {
+            let index = connections.fetch_add(1, Ordering::SeqCst);
+            if DROPPED_PROBES.contains(&index) {
+                stream.peek(&mut [0_u8; 1]).unwrap();
+                return;  // closing with the client hello unread resets the connection
+            }
+            read_client_hello(&mut stream);
+            stream.write_all(&server_hello()).unwrap();
+        })
+    }
+
+    #[test]
+    fn test_default_failure_policy_is_strict() {
+        assert_eq!(Jarm::default().failure_policy, FailurePolicy::Strict);
+        assert_eq!(Scanner::default().failure_policy, FailurePolicy::Strict);
+    }
+
+    #[test]
+    fn test_strict_policy_fails_on_dropped_probe() {
+        let address = spawn_dropping_server();
+
+        let error = jarm_with_policy(address.port(), FailurePolicy::Strict).hash().err().unwrap();
+
+        assert!(matches!(error, JarmError::Io(_)));
+    }
+
+    #[test]
+    fn test_reference_policy_records_dropped_probes() {
+        let address = spawn_dropping_server();
+        let mut jarm = jarm_with_policy(address.port(), FailurePolicy::Reference);
+
+        let hash = jarm.hash().unwrap();
+
+        let server_hello_part = JarmPart::from_str("c02b|0303|h2|0000-0017-ff01-000b-0023-0010").unwrap();
+        let expected_parts: Vec<JarmPart> = (0..10)
+            .map(|index| if DROPPED_PROBES.contains(&index) { JarmPart::NoResponse } else { server_hello_part.clone() })
+            .collect();
+        assert_eq!(jarm.parts, expected_parts);
+        let mut expected = Jarm::default();
+        expected.parts = expected_parts;
+        assert_eq!(hash, expected.hash().unwrap());
+    }
+
+    #[test]
+    fn test_reference_policy_without_server() {
+        let hash = jarm_with_policy(closed_port(), FailurePolicy::Reference).hash().unwrap();
+
+        assert_eq!(hash, "0".repeat(62));
+    }
+
+    #[test]
+    fn test_scanner_failure_policy() {
+        let port = closed_port().to_string();
+        let scanner = Scanner { failure_policy: FailurePolicy::Reference, ..Scanner::new(2) };
+
+        let results: Vec<_> = scanner.scan(vec![("127.0.0.1".to_string(), port)]).collect();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].1.as_ref().unwrap(), &"0".repeat(62));
+    }
+}
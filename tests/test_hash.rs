@@ -0,0 +1,86 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use rust_jarm::error::JarmError;
+    use rust_jarm::hash::{JarmHash, ProbeFingerprint};
+    use crate::common::SERVER_HELLO_HASH;
+
+    const GOOGLE_HASH: &str = "27d40d40d29d40d1dc42d43d00041d4689ee210389f4f6b4b5b1b93f92252d";
+
+    #[test]
+    fn test_parse_hash() {
+        let hash: JarmHash = GOOGLE_HASH.parse().unwrap();
+
+        assert_eq!(hash.to_string(), GOOGLE_HASH);
+        assert_eq!(hash.extensions_digest(), "4689ee210389f4f6b4b5b1b93f92252d");
+        assert!(!hash.is_no_tls());
+        let ciphers: Vec<u8> = hash.probes().iter().map(|probe| probe.cipher_index).collect();
+        assert_eq!(ciphers, vec![0x27, 0x40, 0x40, 0x29, 0x40, 0x1d, 0x42, 0x43, 0x00, 0x41]);
+        let versions: String = hash.probes().iter().map(|probe| probe.version).collect();
+        assert_eq!(versions, "dddddcdd0d");
+    }
+
+    #[rstest]
+    #[case(0, Some(0xc02b), Some(0x0303))]
+    #[case(3, Some(0xc02f), Some(0x0303))]
+    #[case(5, Some(0xc009), Some(0x0302))]
+    #[case(6, Some(0x1302), Some(0x0303))]
+    #[case(8, None, None)]
+    fn test_probe_fingerprint(#[case] index: usize, #[case] cipher_suite: Option<u16>, #[case] protocol_version: Option<u16>) {
+        let probe = GOOGLE_HASH.parse::<JarmHash>().unwrap().probe(index).unwrap();
+
+        assert_eq!(probe.cipher_suite(), cipher_suite);
+        assert_eq!(probe.protocol_version(), protocol_version);
+        assert_eq!(probe.is_no_response(), cipher_suite.is_none());
+    }
+
+    #[test]
+    fn test_probe_out_of_range() {
+        assert_eq!(GOOGLE_HASH.parse::<JarmHash>().unwrap().probe(10), None);
+    }
+
+    #[test]
+    fn test_unknown_cipher() {
+        let probe = ProbeFingerprint { cipher_index: 70, version: 'e' };
+
+        assert_eq!(probe.cipher_suite(), None);
+        assert_eq!(probe.protocol_version(), Some(0x0304));
+        assert!(!probe.is_no_response());
+    }
+
+    #[test]
+    fn test_no_tls_hash() {
+        let hash: JarmHash = "0".repeat(62).parse().unwrap();
+
+        assert_eq!(hash, JarmHash::no_tls());
+        assert!(hash.is_no_tls());
+        assert!(hash.probes().iter().all(ProbeFingerprint::is_no_response));
+    }
+
+    #[test]
+    fn test_parse_uppercase_hash() {
+        let hash: JarmHash = SERVER_HELLO_HASH.to_uppercase().parse().unwrap();
+
+        assert_eq!(hash.as_str(), SERVER_HELLO_HASH);
+    }
+
+    #[rstest]
+    #[case("27d40d40d29d40d1dc42d43d00041d4689ee210389f4f6b4b5b1b93f92252", "a jarm hash is 62 characters long")]
+    #[case("", "a jarm hash is 62 characters long")]
+    #[case("27d40d40d29d40d1dc42d43d00041d4689ee210389f4f6b4b5b1b93f92252g", "a jarm hash is made of hexadecimal characters")]
+    #[case("47d40d40d29d40d1dc42d43d00041d4689ee210389f4f6b4b5b1b93f92252d", "cipher index out of the jarm cipher list")]
+    #[case("27940d40d29d40d1dc42d43d00041d4689ee210389f4f6b4b5b1b93f92252d", "version is not between 'a' and 'f'")]
+    #[case("27040d40d29d40d1dc42d43d00041d4689ee210389f4f6b4b5b1b93f92252d", "cipher and version disagree on the probe having a response")]
+    #[case("27d40d40d29d40d1dc42d43d00d41d4689ee210389f4f6b4b5b1b93f92252d", "cipher and version disagree on the probe having a response")]
+    #[case("0000000000000000000000000000004689ee210389f4f6b4b5b1b93f92252d", "extensions digest without any response")]
+    fn test_parse_invalid_hash(#[case] value: &str, #[case] expected_reason: &str) {
+        let error = value.parse::<JarmHash>().err().unwrap();
+
+        if let JarmError::InvalidHash { hash, reason } = error {
+            assert_eq!(hash, value);
+            assert_eq!(reason, expected_reason);
+        } else { panic!("unexpected type") }
+    }
+}
@@ -0,0 +1,100 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use rust_jarm::hash::{JarmHash, ProbeFingerprint};
+    use rust_jarm::iana::{cipher_suite_name, explain_hash, explain_parts, extension_name, version_name};
+    use rust_jarm::JarmPart;
+
+    #[test]
+    fn test_every_jarm_cipher_is_named() {
+        for cipher_index in 1..=69 {
+            let cipher = ProbeFingerprint { cipher_index, version: 'd' }.cipher_suite().unwrap();
+            assert!(cipher_suite_name(cipher).is_some(), "no name for {cipher:04x}");
+        }
+    }
+
+    #[rstest]
+    #[case(0xc02f, Some("TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256"))]
+    #[case(0x1301, Some("TLS_AES_128_GCM_SHA256"))]
+    #[case(0xcc13, Some("TLS_ECDHE_RSA_WITH_CHACHA20_POLY1305_SHA256_OLD"))]
+    #[case(0x00ff, None)]
+    fn test_cipher_suite_name(#[case] cipher: u16, #[case] expected: Option<&str>) {
+        assert_eq!(cipher_suite_name(cipher), expected);
+    }
+
+    #[rstest]
+    #[case('a', "SSL 3.0")]
+    #[case('b', "TLS 1.0")]
+    #[case('c', "TLS 1.1")]
+    #[case('d', "TLS 1.2")]
+    #[case('e', "TLS 1.3")]
+    fn test_version_name(#[case] version: char, #[case] expected: &str) {
+        let version = ProbeFingerprint { cipher_index: 1, version }.protocol_version().unwrap();
+        assert_eq!(version_name(version), Some(expected));
+    }
+
+    #[test]
+    fn test_unknown_version_name() {
+        assert_eq!(version_name(0x0305), None);
+    }
+
+    #[rstest]
+    #[case(0x0000, Some("server_name"))]
+    #[case(0x0010, Some("application_layer_protocol_negotiation"))]
+    #[case(0x0017, Some("extended_master_secret"))]
+    #[case(0x002b, Some("supported_versions"))]
+    #[case(0x0033, Some("key_share"))]
+    #[case(0xff01, Some("renegotiation_info"))]
+    #[case(0x3a3a, Some("GREASE"))]
+    #[case(0x3a4a, None)]
+    #[case(0x0028, None)]
+    fn test_extension_name(#[case] extension: u16, #[case] expected: Option<&str>) {
+        assert_eq!(extension_name(extension), expected);
+    }
+
+    #[test]
+    fn test_explain_hash() {
+        let hash: JarmHash = "27d40d40d29d40d1dc42d43d00046d4689ee210389f4f6b4b5b1b93f92252d".parse().unwrap();
+
+        let expected = "\
+tls1_2_forward: TLS_ECDHE_ECDSA_WITH_AES_128_GCM_SHA256 (0xc02b), TLS 1.2 (0x0303)
+tls1_2_reverse: TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256 (0xcca9), TLS 1.2 (0x0303)
+tls1_2_top_half: TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256 (0xcca9), TLS 1.2 (0x0303)
+tls1_2_bottom_half: TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256 (0xc02f), TLS 1.2 (0x0303)
+tls1_2_middle_out: TLS_ECDHE_ECDSA_WITH_CHACHA20_POLY1305_SHA256 (0xcca9), TLS 1.2 (0x0303)
+tls1_1_middle_out: TLS_ECDHE_ECDSA_WITH_AES_128_CBC_SHA (0xc009), TLS 1.1 (0x0302)
+tls1_3_forward: TLS_AES_256_GCM_SHA384 (0x1302), TLS 1.2 (0x0303)
+tls1_3_reverse: TLS_CHACHA20_POLY1305_SHA256 (0x1303), TLS 1.2 (0x0303)
+tls1_3_invalid: no response
+tls1_3_middle_out: cipher suite missing from the jarm list, TLS 1.2 (0x0303)
+ALPN and extensions digest: 4689ee210389f4f6b4b5b1b93f92252d";
+        assert_eq!(explain_hash(&hash), expected);
+    }
+
+    #[test]
+    fn test_explain_no_tls_hash() {
+        let explanation = explain_hash(&JarmHash::no_tls());
+
+        assert_eq!(explanation.lines().filter(|line| line.ends_with(": no response")).count(), 10);
+    }
+
+    #[test]
+    fn test_explain_parts() {
+        let mut parts = vec![JarmPart::NoResponse; 10];
+        parts[0] = "c02f|0303|h2|ff01-0000-0001-000b-0023-0010-0017".parse().unwrap();
+        parts[6] = "1301|0303||002b-0033-abcd".parse().unwrap();
+
+        let explanation = explain_parts(&parts);
+        let lines: Vec<&str> = explanation.lines().collect();
+
+        assert_eq!(lines.len(), 10);
+        assert_eq!(lines[0], "tls1_2_forward: TLS_ECDHE_RSA_WITH_AES_128_GCM_SHA256 (0xc02f), TLS 1.2 (0x0303), ALPN h2, extensions \
+            [renegotiation_info (0xff01), server_name (0x0000), max_fragment_length (0x0001), ec_point_formats (0x000b), \
+            session_ticket (0x0023), application_layer_protocol_negotiation (0x0010), extended_master_secret (0x0017)]");
+        assert_eq!(lines[1], "tls1_2_reverse: no response");
+        assert_eq!(lines[6], "tls1_3_forward: TLS_AES_128_GCM_SHA256 (0x1301), TLS 1.2 (0x0303), extensions \
+            [supported_versions (0x002b), key_share (0x0033), unknown (0xabcd)]");
+    }
+}
@@ -0,0 +1,149 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use rstest::*;
+    use rust_jarm::error::JarmError;
+    use rust_jarm::import::{from_masscan_json, from_masscan_list, from_nmap_xml, ServiceFilter};
+    use rust_jarm::target::Target;
+    use rust_jarm::{Jarm, TestRng};
+    use crate::common::{server_hello, CannedTransport, SERVER_HELLO_HASH};
+
+    const NMAP_XML: &str = include_str!("fixtures/nmap.xml");
+    const MASSCAN_JSON: &str = include_str!("fixtures/masscan.json");
+    const MASSCAN_LIST: &str = include_str!("fixtures/masscan.txt");
+
+    fn target(target: &str) -> Target {
+        target.parse().unwrap()
+    }
+
+    fn scanme(port: u16) -> Target {
+        Target::new("scanme.example", port).unwrap().with_address(format!("192.0.2.1:{port}").parse().unwrap())
+    }
+
+    #[rstest]
+    #[case(ServiceFilter::Any, vec![
+        scanme(22), scanme(80), scanme(443),
+        target("192.0.2.10:443"), target("192.0.2.10:993"), target("192.0.2.10:8443"),
+        target("[2001:db8::10]:443"),
+    ])]
+    #[case(ServiceFilter::Tls, vec![
+        scanme(443),
+        target("192.0.2.10:443"), target("192.0.2.10:993"), target("192.0.2.10:8443"),
+        target("[2001:db8::10]:443"),
+    ])]
+    fn test_nmap_xml(#[case] filter: ServiceFilter, #[case] expected: Vec<Target>) {
+        assert_eq!(from_nmap_xml(NMAP_XML, filter).unwrap().targets, expected);
+    }
+
+    #[test]
+    fn test_masscan_json() {
+        let expected = vec![target("192.0.2.1:443"), target("192.0.2.10:8443"), target("[2001:db8::10]:443")];
+        assert_eq!(from_masscan_json(MASSCAN_JSON).unwrap().targets, expected);
+    }
+
+    #[test]
+    fn test_masscan_json_trailing_comma() {
+        let json = "[\n{   \"ip\": \"192.0.2.1\",   \"timestamp\": \"1728742011\", \"ports\": [ {\"port\": 443, \"proto\": \"tcp\", \"status\": \"open\", \"reason\": \"syn-ack\", \"ttl\": 53} ] },\n]\n";
+        assert_eq!(from_masscan_json(json).unwrap().targets, vec![target("192.0.2.1:443")]);
+    }
+
+    #[test]
+    fn test_masscan_list() {
+        let expected = vec![target("192.0.2.1:443"), target("192.0.2.10:8443"), target("[2001:db8::10]:443")];
+        assert_eq!(from_masscan_list(MASSCAN_LIST).targets, expected);
+    }
+
+    #[rstest]
+    #[case("", Vec::new())]
+    #[case("<nmaprun></nmaprun>", Vec::new())]
+    #[case("[]", Vec::new())]
+    fn test_empty_outputs(#[case] output: &str, #[case] expected: Vec<Target>) {
+        let targets = match output.starts_with('[') {
+            true => from_masscan_json(output),
+            false => from_nmap_xml(output, ServiceFilter::Any),
+        };
+        assert_eq!(targets.unwrap().targets, expected);
+    }
+
+    #[rstest]
+    #[case("<nmaprun><host", 9, "unterminated XML tag")]
+    #[case("<nmaprun><!-- comment", 9, "unterminated XML declaration or comment")]
+    #[case("<nmaprun><host starttime=1728741731>", 9, "invalid XML attribute")]
+    fn test_invalid_nmap_xml(#[case] xml: &str, #[case] expected_offset: usize, #[case] expected_reason: &str) {
+        let error = from_nmap_xml(xml, ServiceFilter::Any).err().unwrap();
+
+        if let JarmError::ScanOutput { offset, reason } = error {
+            assert_eq!(offset, expected_offset);
+            assert_eq!(reason, expected_reason);
+        } else { panic!("unexpected type") }
+    }
+
+    #[rstest]
+    #[case("{}", 0, "masscan output is not a JSON array")]
+    #[case("[{\"ip\": \"192.0.2.1\"", 19, "expected ',' or '}' in a JSON object")]
+    #[case("[{\"ip\": tru}]", 8, "invalid JSON literal")]
+    #[case("[{\"ip\": \"192.0.2.1\"}] x", 22, "unexpected data after the JSON document")]
+    #[case("[{\"ip\": \"\\ud800\"}]", 14, "unpaired surrogate in JSON string")]
+    #[case(&"[".repeat(200_000), 128, "JSON nested too deeply")]
+    fn test_invalid_masscan_json(#[case] json: &str, #[case] expected_offset: usize, #[case] expected_reason: &str) {
+        let error = from_masscan_json(json).err().unwrap();
+
+        if let JarmError::ScanOutput { offset, reason } = error {
+            assert_eq!(offset, expected_offset);
+            assert_eq!(reason, expected_reason);
+        } else { panic!("unexpected type") }
+    }
+
+    #[test]
+    fn test_invalid_port_in_masscan_list() {
+        let imported = from_masscan_list("#masscan\nopen tcp https 192.0.2.1 1728742011\nopen tcp 443 192.0.2.1 1728742011\n");
+
+        assert_eq!(imported.targets, vec![target("192.0.2.1:443")]);
+        assert!(matches!(imported.skipped[..], [JarmError::ScanOutput { offset: 9, reason: "invalid port in masscan output" }]));
+    }
+
+    #[test]
+    fn test_invalid_address_in_masscan_list() {
+        let imported = from_masscan_list("open tcp 443 192.0.2.1/24 1728742011\nopen tcp 443 192.0.2.2 1728742011\n");
+
+        assert_eq!(imported.targets, vec![target("192.0.2.2:443")]);
+        assert!(matches!(imported.skipped[..], [JarmError::InvalidTarget { reason: "invalid character in host name", .. }]));
+    }
+
+    #[test]
+    fn test_invalid_host_name_in_nmap_xml() {
+        let xml = concat!(
+            "<nmaprun><host><address addr=\"192.0.2.1\" addrtype=\"ipv4\"/>",
+            "<hostnames><hostname name=\"bad_name..example\" type=\"user\"/></hostnames>",
+            "<ports><port protocol=\"tcp\" portid=\"443\"><state state=\"open\"/></port></ports></host>",
+            "<host><address addr=\"192.0.2.2\" addrtype=\"ipv4\"/>",
+            "<ports><port protocol=\"tcp\" portid=\"443\"><state state=\"open\"/></port></ports></host></nmaprun>",
+        );
+
+        let imported = from_nmap_xml(xml, ServiceFilter::Any).unwrap();
+
+        assert_eq!(imported.targets, vec![target("192.0.2.2:443")]);
+        assert!(matches!(imported.skipped[..], [JarmError::InvalidTarget { .. }]));
+    }
+
+    #[test]
+    fn test_duplicates_are_imported_once() {
+        let list = "open tcp 443 192.0.2.1 1728742011\nopen tcp 8443 192.0.2.1 1728742011\nopen tcp 443 192.0.2.1 1728742012\n";
+
+        assert_eq!(from_masscan_list(list).targets, vec![target("192.0.2.1:443"), target("192.0.2.1:8443")]);
+    }
+
+    #[test]
+    fn test_scan_imported_target() {
+        let transport = Arc::new(CannedTransport::new(server_hello()));
+        let target = from_nmap_xml(NMAP_XML, ServiceFilter::Tls).unwrap().targets.remove(0);
+        let mut jarm = Jarm::from(target);
+        jarm.rng = Box::new(TestRng {});
+        jarm.transport = transport.clone();
+
+        assert_eq!(jarm.hash().unwrap(), SERVER_HELLO_HASH);
+        assert!(transport.client_hellos.lock().unwrap().iter().all(|(address, _)| *address == "192.0.2.1:443".parse().unwrap()));
+    }
+}
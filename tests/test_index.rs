@@ -0,0 +1,136 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use rstest::*;
+    use rust_jarm::error::JarmError;
+    use rust_jarm::hash::JarmHash;
+    use rust_jarm::index::{distance, HashIndex, Neighbour};
+
+    const GOOGLE_HASH: &str = "27d40d40d29d40d1dc42d43d00041d4689ee210389f4f6b4b5b1b93f92252d";
+    const ONE_PROBE_AWAY: &str = "28d40d40d29d40d1dc42d43d00041d4689ee210389f4f6b4b5b1b93f92252d";
+    const OTHER_DIGEST: &str = "27d40d40d29d40d1dc42d43d00041d00000000000000000000000000000001";
+    const TWO_PROBES_AWAY: &str = "28d40d40d29d40d1dc42d43d00000000000000000000000000000000000001";
+    const FAR_AWAY: &str = "2ae2ae2ae2ae2ae2ae2ae2ae2ae2ae00000000000000000000000000000001";
+
+    fn hash(hash: &str) -> JarmHash {
+        hash.parse().unwrap()
+    }
+
+    fn index() -> HashIndex {
+        [GOOGLE_HASH, ONE_PROBE_AWAY, OTHER_DIGEST, TWO_PROBES_AWAY, FAR_AWAY].into_iter().map(hash).collect()
+    }
+
+    fn neighbour(value: &str, distance: usize) -> Neighbour {
+        Neighbour { hash: hash(value), distance }
+    }
+
+    #[rstest]
+    #[case(GOOGLE_HASH, ONE_PROBE_AWAY, 1)]
+    #[case(GOOGLE_HASH, OTHER_DIGEST, 0)]
+    #[case(GOOGLE_HASH, TWO_PROBES_AWAY, 2)]
+    #[case(GOOGLE_HASH, FAR_AWAY, 10)]
+    fn test_distance(#[case] a: &str, #[case] b: &str, #[case] expected: usize) {
+        assert_eq!(distance(&hash(a), &hash(b)), expected);
+        assert_eq!(distance(&hash(b), &hash(a)), expected);
+    }
+
+    #[test]
+    fn test_insert_and_lookup() {
+        let mut index = HashIndex::new();
+        assert!(index.is_empty());
+
+        assert!(index.insert(&hash(GOOGLE_HASH)));
+        assert!(index.insert(&hash(OTHER_DIGEST)));
+        assert!(!index.insert(&hash(GOOGLE_HASH)));
+
+        assert_eq!(index.len(), 2);
+        assert!(index.contains(&hash(GOOGLE_HASH)));
+        assert!(index.contains(&hash(OTHER_DIGEST)));
+        assert!(!index.contains(&hash(ONE_PROBE_AWAY)));
+        assert!(!HashIndex::new().contains(&hash(GOOGLE_HASH)));
+    }
+
+    #[rstest]
+    #[case(0, vec![neighbour(OTHER_DIGEST, 0), neighbour(GOOGLE_HASH, 0)])]
+    #[case(1, vec![neighbour(OTHER_DIGEST, 0), neighbour(GOOGLE_HASH, 0), neighbour(ONE_PROBE_AWAY, 1)])]
+    #[case(2, vec![
+        neighbour(OTHER_DIGEST, 0), neighbour(GOOGLE_HASH, 0), neighbour(ONE_PROBE_AWAY, 1), neighbour(TWO_PROBES_AWAY, 2),
+    ])]
+    fn test_within(#[case] radius: usize, #[case] expected: Vec<Neighbour>) {
+        assert_eq!(index().within(&hash(GOOGLE_HASH), radius), expected);
+    }
+
+    #[test]
+    fn test_within_everything() {
+        assert_eq!(index().within(&hash(GOOGLE_HASH), 10).len(), 5);
+        assert_eq!(HashIndex::new().within(&hash(GOOGLE_HASH), 10), Vec::new());
+    }
+
+    #[test]
+    fn test_serialization() {
+        let index = index();
+
+        let bytes = index.to_bytes();
+        let loaded = HashIndex::from_bytes(&bytes).unwrap();
+
+        assert_eq!(bytes.len(), 16 + 5 * 31);
+        assert_eq!(loaded.len(), 5);
+        assert_eq!(loaded.to_bytes(), bytes);
+        assert_eq!(loaded.within(&hash(GOOGLE_HASH), 1), index.within(&hash(GOOGLE_HASH), 1));
+    }
+
+    #[test]
+    fn test_serialization_of_empty_index() {
+        assert!(HashIndex::from_bytes(&HashIndex::new().to_bytes()).unwrap().is_empty());
+    }
+
+    #[rstest]
+    #[case(b"JARMIDX\x02".to_vec(), 0, "not a hash index or unsupported version")]
+    #[case(b"JARMIDX\x01\x01".to_vec(), 8, "truncated header")]
+    #[case([&b"JARMIDX\x01\x02\0\0\0\0\0\0\0"[..], &[0; 31]].concat(), 16, "the number of hashes doesn't match the header")]
+    #[case([&b"JARMIDX\x01\x01\0\0\0\0\0\0\0"[..], &[0xff; 31]].concat(), 16, "invalid jarm hash")]
+    #[case([&b"JARMIDX\x02\x02\0\0\0\0\0\0\0"[..], &[0; 62]].concat(), 0, "not a hash index or unsupported version")]
+    #[case([&b"JARMIDX\x01\x02\0\0\0\0\0\0\0"[..], &[0; 62]].concat(), 47, "duplicated jarm hash")]
+    fn test_invalid_serialization(#[case] bytes: Vec<u8>, #[case] expected_offset: usize, #[case] expected_reason: &str) {
+        let error = HashIndex::from_bytes(&bytes).err().unwrap();
+
+        if let JarmError::HashIndex { offset, reason } = error {
+            assert_eq!(offset, expected_offset);
+            assert_eq!(reason, expected_reason);
+        } else { panic!("unexpected type") }
+    }
+
+    fn arbitrary_hash() -> impl Strategy<Value = JarmHash> {
+        // Few distinct values per probe so hashes share probes
+        let probe = prop_oneof![Just("000"), Just("27d"), Just("28d"), Just("29e"), Just("3fd")];
+        (proptest::collection::vec(probe, 10), prop_oneof![Just("0"), Just("1")]).prop_map(|(probes, digest)| {
+            let digest = match probes.iter().all(|probe| *probe == "000") {
+                true => "0",
+                false => digest,
+            };
+            format!("{}{}", probes.concat(), digest.repeat(32)).parse().unwrap()
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn within_matches_exhaustive_search(
+            hashes in proptest::collection::vec(arbitrary_hash(), 0..64),
+            query in arbitrary_hash(),
+            radius in 0usize..=10,
+        ) {
+            let index: HashIndex = hashes.iter().cloned().collect();
+            let mut expected: Vec<Neighbour> = hashes.iter()
+                .map(|hash| Neighbour { hash: hash.clone(), distance: distance(&query, hash) })
+                .filter(|neighbour| neighbour.distance <= radius)
+                .collect();
+            expected.sort_by_key(|neighbour| (neighbour.distance, neighbour.hash.clone()));
+            expected.dedup();
+
+            prop_assert_eq!(index.within(&query, radius), expected);
+            prop_assert!(hashes.iter().all(|hash| index.contains(hash)));
+        }
+    }
+}
@@ -1,8 +1,13 @@
+mod common;
+
 #[cfg(test)]
 mod tests {
+    use std::str::FromStr;
     use rstest::rstest;
 
+    use rust_jarm::error::JarmError;
     use rust_jarm::{cipher_bytes, CipherList, CipherOrder, Jarm, JarmPart, JarmRng, PacketSpecification, TestRng, TlsVersion, TlsVersionSupport, version_byte};
+    use rust_jarm::sni::ServerName;
 
     fn test_rng() -> TestRng {
         TestRng {}
@@ -14,16 +19,16 @@ mod tests {
 
         let mut jarm = Jarm::default();
         jarm.parts = vec![
-            JarmPart::new("c02b|0303|h2|0000-0017-ff01-000b-0023-0010"),
-            JarmPart::new("c02b|0303|h2|0000-0017-ff01-000b-0023-0010"),
-            JarmPart::new("c02b|0303|h2|0000-0017-ff01-000b-0023-0010"),
-            JarmPart::new("c02b|0303|h2|0000-0017-ff01-000b-0023-0010"),
-            JarmPart::new("c02b|0303|h2|0000-0017-ff01-000b-0023-0010"),
-            JarmPart::new("c02b|0303|h2|0000-0017-ff01-000b-0023-0010"),
-            JarmPart::new("c02b|0303|h2|0000-0017-ff01-000b-0023-0010"),
-            JarmPart::new("c02b|0303|h2|0000-0017-ff01-000b-0023-0010"),
-            JarmPart::new("c02b|0303|h2|0000-0017-ff01-000b-0023-0010"),
-            JarmPart::new("c02b|0303|h2|0000-0017-ff01-000b-0023-0010"),
+            JarmPart::from_str("c02b|0303|h2|0000-0017-ff01-000b-0023-0010").unwrap(),
+            JarmPart::from_str("c02b|0303|h2|0000-0017-ff01-000b-0023-0010").unwrap(),
+            JarmPart::from_str("c02b|0303|h2|0000-0017-ff01-000b-0023-0010").unwrap(),
+            JarmPart::from_str("c02b|0303|h2|0000-0017-ff01-000b-0023-0010").unwrap(),
+            JarmPart::from_str("c02b|0303|h2|0000-0017-ff01-000b-0023-0010").unwrap(),
+            JarmPart::from_str("c02b|0303|h2|0000-0017-ff01-000b-0023-0010").unwrap(),
+            JarmPart::from_str("c02b|0303|h2|0000-0017-ff01-000b-0023-0010").unwrap(),
+            JarmPart::from_str("c02b|0303|h2|0000-0017-ff01-000b-0023-0010").unwrap(),
+            JarmPart::from_str("c02b|0303|h2|0000-0017-ff01-000b-0023-0010").unwrap(),
+            JarmPart::from_str("c02b|0303|h2|0000-0017-ff01-000b-0023-0010").unwrap(),
         ];
         jarm.rng = Box::new(TestRng {});  // use the mock rng
 
@@ -54,10 +59,10 @@ mod tests {
 
     #[test]
     fn test_version_byte() {
-        assert_eq!(version_byte(""), '0');
-        assert_eq!(version_byte("0301"), 'b');
-        assert_eq!(version_byte("0303"), 'd');
-        assert_eq!(version_byte("0304"), 'e');
+        assert_eq!(version_byte("").unwrap(), '0');
+        assert_eq!(version_byte("0301").unwrap(), 'b');
+        assert_eq!(version_byte("0303").unwrap(), 'd');
+        assert_eq!(version_byte("0304").unwrap(), 'e');
     }
 
     #[test]
@@ -66,16 +71,16 @@ mod tests {
 
         let mut jarm = Jarm::default();
         jarm.parts = vec![
-            JarmPart::new("|||"),
-            JarmPart::new("|||"),
-            JarmPart::new("|||"),
-            JarmPart::new("|||"),
-            JarmPart::new("|||"),
-            JarmPart::new("|||"),
-            JarmPart::new("|||"),
-            JarmPart::new("|||"),
-            JarmPart::new("|||"),
-            JarmPart::new("|||"),
+            JarmPart::from_str("|||").unwrap(),
+            JarmPart::from_str("|||").unwrap(),
+            JarmPart::from_str("|||").unwrap(),
+            JarmPart::from_str("|||").unwrap(),
+            JarmPart::from_str("|||").unwrap(),
+            JarmPart::from_str("|||").unwrap(),
+            JarmPart::from_str("|||").unwrap(),
+            JarmPart::from_str("|||").unwrap(),
+            JarmPart::from_str("|||").unwrap(),
+            JarmPart::from_str("|||").unwrap(),
         ];
 
         assert_eq!(jarm.hash().unwrap(), expected_hash);
@@ -89,6 +94,7 @@ mod tests {
         let tls_1_2_spec = PacketSpecification {
             host: "jsonplaceholder.typicode.com".to_string(),
             port: "443".to_string(),
+            server_name: ServerName::Host,
             tls_version: TlsVersion::TLS1_2,
             cipher_list: CipherList::ALL,
             cipher_order: CipherOrder::FORWARD,
@@ -109,6 +115,7 @@ mod tests {
         let tls_1_2_spec = PacketSpecification {
             host: "jsonplaceholder.typicode.com".to_string(),
             port: "443".to_string(),
+            server_name: ServerName::Host,
             tls_version: TlsVersion::TLS1_2,
             cipher_list: CipherList::ALL,
             cipher_order: CipherOrder::REVERSE,
@@ -129,6 +136,7 @@ mod tests {
         let tls_1_2_spec = PacketSpecification {
             host: "jsonplaceholder.typicode.com".to_string(),
             port: "443".to_string(),
+            server_name: ServerName::Host,
             tls_version: TlsVersion::TLS1_2,
             cipher_list: CipherList::ALL,
             cipher_order: CipherOrder::TOP_HALF,
@@ -149,6 +157,7 @@ mod tests {
         let tls_1_2_spec = PacketSpecification {
             host: "jsonplaceholder.typicode.com".to_string(),
             port: "443".to_string(),
+            server_name: ServerName::Host,
             tls_version: TlsVersion::TLS1_2,
             cipher_list: CipherList::ALL,
             cipher_order: CipherOrder::BOTTOM_HALF,
@@ -169,6 +178,7 @@ mod tests {
         let tls_1_2_spec = PacketSpecification {
             host: "jsonplaceholder.typicode.com".to_string(),
             port: "443".to_string(),
+            server_name: ServerName::Host,
             tls_version: TlsVersion::TLS1_2,
             cipher_list: CipherList::ALL,
             cipher_order: CipherOrder::MIDDLE_OUT,
@@ -189,6 +199,7 @@ mod tests {
         let tls_1_1_spec = PacketSpecification {
             host: "jsonplaceholder.typicode.com".to_string(),
             port: "443".to_string(),
+            server_name: ServerName::Host,
             tls_version: TlsVersion::TLS1_1,
             cipher_list: CipherList::ALL,
             cipher_order: CipherOrder::FORWARD,
@@ -209,6 +220,7 @@ mod tests {
         let tls_1_3_spec = PacketSpecification {
             host: "jsonplaceholder.typicode.com".to_string(),
             port: "443".to_string(),
+            server_name: ServerName::Host,
             tls_version: TlsVersion::TLS1_3,
             cipher_list: CipherList::ALL,
             cipher_order: CipherOrder::FORWARD,
@@ -229,6 +241,7 @@ mod tests {
         let tls_1_3_spec = PacketSpecification {
             host: "jsonplaceholder.typicode.com".to_string(),
             port: "443".to_string(),
+            server_name: ServerName::Host,
             tls_version: TlsVersion::TLS1_3,
             cipher_list: CipherList::ALL,
             cipher_order: CipherOrder::REVERSE,
@@ -249,6 +262,7 @@ mod tests {
         let tls_1_3_spec = PacketSpecification {
             host: "jsonplaceholder.typicode.com".to_string(),
             port: "443".to_string(),
+            server_name: ServerName::Host,
             tls_version: TlsVersion::TLS1_3,
             cipher_list: CipherList::NO1_3,
             cipher_order: CipherOrder::FORWARD,
@@ -269,6 +283,7 @@ mod tests {
         let tls_1_3_spec = PacketSpecification {
             host: "jsonplaceholder.typicode.com".to_string(),
             port: "443".to_string(),
+            server_name: ServerName::Host,
             tls_version: TlsVersion::TLS1_3,
             cipher_list: CipherList::ALL,
             cipher_order: CipherOrder::MIDDLE_OUT,
@@ -289,6 +304,7 @@ mod tests {
         let tls_1_2_spec = PacketSpecification {
             host: "jsonplaceholder.typicode.com".to_string(),
             port: "443".to_string(),
+            server_name: ServerName::Host,
             tls_version: TlsVersion::TLS1_2,
             cipher_list: CipherList::ALL,
             cipher_order: CipherOrder::FORWARD,
@@ -309,6 +325,7 @@ mod tests {
         let tls_1_2_spec = PacketSpecification {
             host: "jsonplaceholder.typicode.com".to_string(),
             port: "443".to_string(),
+            server_name: ServerName::Host,
             tls_version: TlsVersion::TLS1_2,
             cipher_list: CipherList::ALL,
             cipher_order: CipherOrder::MIDDLE_OUT,
@@ -329,6 +346,7 @@ mod tests {
         let tls_1_2_spec = PacketSpecification {
             host: "jsonplaceholder.typicode.com".to_string(),
             port: "443".to_string(),
+            server_name: ServerName::Host,
             tls_version: TlsVersion::TLS1_2,
             cipher_list: CipherList::ALL,
             cipher_order: CipherOrder::FORWARD,
@@ -349,6 +367,7 @@ mod tests {
         let tls_1_2_spec = PacketSpecification {
             host: "jsonplaceholder.typicode.com".to_string(),
             port: "443".to_string(),
+            server_name: ServerName::Host,
             tls_version: TlsVersion::TLS1_2,
             cipher_list: CipherList::ALL,
             cipher_order: CipherOrder::FORWARD,
@@ -369,6 +388,7 @@ mod tests {
         let tls_1_2_spec = PacketSpecification {
             host: "jsonplaceholder.typicode.com".to_string(),
             port: "443".to_string(),
+            server_name: ServerName::Host,
             tls_version: TlsVersion::TLS1_2,
             cipher_list: CipherList::ALL,
             cipher_order: CipherOrder::FORWARD,
@@ -587,6 +607,7 @@ mod tests {
         let tls_1_2_spec = PacketSpecification {
             host: "jsonplaceholder.typicode.com".to_string(),
             port: "443".to_string(),
+            server_name: ServerName::Host,
             tls_version: TlsVersion::TLS1_2,
             cipher_list: CipherList::ALL,
             cipher_order: CipherOrder::FORWARD,
@@ -605,9 +626,122 @@ mod tests {
         let input_packet = hex::decode(input_hex).unwrap();
         let expected_result = "c02b|0303|h2|0000-0017-ff01-000b-0023-0010";
 
-        let jarm = rust_jarm::read_packet(input_packet);
+        let jarm = rust_jarm::read_packet(input_packet).unwrap();
+
+        assert_eq!(jarm.to_string(), expected_result);
+    }
+
+    #[test]
+    fn test_read_packet_typed_fields() {
+        let input_packet = hex::decode(crate::common::SERVER_HELLO_HEX).unwrap();
+
+        let part = rust_jarm::read_packet(input_packet).unwrap();
+
+        assert_eq!(part, JarmPart::ServerHello {
+            cipher: 0xc02b,
+            version: 0x0303,
+            alpn: Some("h2".to_string()),
+            extensions: vec![0x0000, 0x0017, 0xff01, 0x000b, 0x0023, 0x0010],
+        });
+    }
+
+    #[test]
+    fn test_read_packet_no_response() {
+        let part = rust_jarm::read_packet(vec![0; 1484]).unwrap();
+
+        assert_eq!(part, JarmPart::NoResponse);
+    }
 
-        assert_eq!(jarm.raw, expected_result);
+    #[rstest]
+    #[case(5, 5)]  // record header only
+    #[case(44, 44)]  // selected cipher missing
+    #[case(60, 59)]  // extensions cut
+    fn test_read_packet_truncated(#[case] length: usize, #[case] expected_offset: usize) {
+        let mut input_packet = hex::decode(crate::common::SERVER_HELLO_HEX).unwrap();
+        input_packet.truncate(length);
+
+        let error = rust_jarm::read_packet(input_packet).err().unwrap();
+
+        if let JarmError::Parse { offset, reason } = error {
+            assert_eq!(offset, expected_offset);
+            assert_eq!(reason, "truncated server hello");
+        } else { panic!("unexpected type") }
+    }
+
+    #[test]
+    fn test_read_packet_extension_length_overflow() {
+        let mut input_packet = hex::decode(crate::common::SERVER_HELLO_HEX).unwrap();
+        input_packet[59] = 0xff;  // length of the renegotiation_info extension
+
+        let error = rust_jarm::read_packet(input_packet).err().unwrap();
+
+        assert!(matches!(error, JarmError::Parse { offset: 61, .. }));
+    }
+
+    #[test]
+    fn test_read_packet_invalid_utf8_alpn() {
+        let mut input_packet = hex::decode(crate::common::SERVER_HELLO_HEX).unwrap();
+        input_packet[79] = 0xff;  // first byte of "h2"
+
+        let error = rust_jarm::read_packet(input_packet).err().unwrap();
+
+        assert!(matches!(error, JarmError::Parse { offset: 79, reason: "invalid UTF-8 ALPN" }));
+    }
+
+    #[test]
+    fn test_empty_response() {
+        assert_eq!(rust_jarm::read_packet(vec![]).unwrap(), JarmPart::NoResponse);
+    }
+
+    #[rstest]
+    #[case("0306")]
+    #[case("03ff")]
+    #[case("03")]
+    fn test_version_byte_unexpected(#[case] version: &str) {
+        let error = version_byte(version).err().unwrap();
+        assert!(matches!(error, JarmError::Parse { offset: 3, reason: "unexpected version" }));
+    }
+
+    #[test]
+    fn test_jarm_hash_unexpected_version() {
+        let mut jarm = Jarm::default();
+        jarm.parts = vec![JarmPart::from_str("c02b|03ff|h2|0000").unwrap(); 10];
+
+        assert!(matches!(jarm.hash(), Err(JarmError::Parse { .. })));
+    }
+
+    #[rstest]
+    #[case("|||")]
+    #[case("c02b|0303|h2|0000-0017-ff01-000b-0023-0010")]
+    #[case("c02b|0303||")]
+    #[case("1301|0303||0033-002b")]
+    #[case("c02f|0303|http/1.1|ff01")]
+    #[case("c02f|0303|odd|alpn|ff01")]
+    fn test_jarm_part_round_trip(#[case] raw: &str) {
+        assert_eq!(JarmPart::from_str(raw).unwrap().to_string(), raw);
+    }
+
+    #[test]
+    fn test_jarm_part_from_str() {
+        assert_eq!(JarmPart::from_str("|||").unwrap(), JarmPart::NoResponse);
+        assert_eq!(JarmPart::from_str("1301|0303||0033-002b").unwrap(), JarmPart::ServerHello {
+            cipher: 0x1301,
+            version: 0x0303,
+            alpn: None,
+            extensions: vec![0x0033, 0x002b],
+        });
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("c02b")]
+    #[case("c02b|0303|h2")]
+    #[case("zzzz|0303|h2|0000")]
+    #[case("c02b|303|h2|0000")]
+    #[case("c02b|0303|h2|0000-")]
+    fn test_jarm_part_from_str_malformed(#[case] raw: &str) {
+        let error = JarmPart::from_str(raw).err().unwrap();
+        assert!(matches!(error, JarmError::MalformedPart(_)));
     }
 
     #[test]
@@ -651,17 +785,19 @@ mod tests {
     }
 
     #[test]
+    #[allow(deprecated)]
     fn test_find_extension() {  // TODO add more example
         let types: Vec<&[u8]> = vec![b"\x00\x00", b"\x00\x17", b"\xff\x01", b"\x00\x0b", b"\x00#", b"\x00\x10"];
         let values: Vec<Option<&[u8]>> = vec![None, None, Some(b"\x00"), Some(b"\x01\x00"), None, Some(b"\x00\x03\x02h2")];
         let expected_result = "h2".to_string();
 
-        let result = rust_jarm::find_extension(&types, values);
+        let result = rust_jarm::find_extension(&types, values).unwrap();
 
         assert_eq!(result, expected_result);
     }
 
     #[test]
+    #[allow(deprecated)]
     fn test_add_formatting_hyphen() {
         let types: Vec<&[u8]> = vec![b"\x00\x00", b"\x00\x17", b"\xff\x01", b"\x00\x0b", b"\x00#", b"\x00\x10"];
         let expected_result = "0000-0017-ff01-000b-0023-0010".to_string();
@@ -682,7 +818,7 @@ mod tests {
         let input_packet = hex::decode(input_hex).unwrap();
         let counter: usize = 0;
 
-        let extension = rust_jarm::extract_extension_info(input_packet, counter);
+        let extension = rust_jarm::extract_extension_info(input_packet, counter).unwrap();
 
         assert_eq!(extension, expected_result);
     }
@@ -694,7 +830,7 @@ mod tests {
         let input_packet = hex::decode(input_hex).unwrap();
         let counter: usize = 32;
 
-        let extension = rust_jarm::extract_extension_info(input_packet, counter);
+        let extension = rust_jarm::extract_extension_info(input_packet, counter).unwrap();
 
         assert_eq!(extension, expected_result);
     }
@@ -706,7 +842,7 @@ mod tests {
         let input_packet = hex::decode(input_hex).unwrap();
         let counter: usize = 32;
 
-        let extension = rust_jarm::extract_extension_info(input_packet, counter);
+        let extension = rust_jarm::extract_extension_info(input_packet, counter).unwrap();
 
         assert_eq!(extension, expected_result);
     }
@@ -722,7 +858,7 @@ mod tests {
         let length_start_index = counter + 47;
         input_packet[length_start_index] = 11;  // Force the value to 11
 
-        let extension = rust_jarm::extract_extension_info(input_packet, counter);
+        let extension = rust_jarm::extract_extension_info(input_packet, counter).unwrap();
 
         assert_eq!(extension, expected_result);
     }
@@ -738,7 +874,7 @@ mod tests {
         let incoming_error_1 = b"\x0e\xac\x0b".to_vec();
         input_packet[(counter+50)..(counter+53)].copy_from_slice(&incoming_error_1[..]);
 
-        let extension = rust_jarm::extract_extension_info(input_packet, counter);
+        let extension = rust_jarm::extract_extension_info(input_packet, counter).unwrap();
 
         assert_eq!(extension, expected_result);
     }
@@ -754,7 +890,7 @@ mod tests {
         let incoming_error_2 = b"\x0f\xf0\x0b".to_vec();
         input_packet[(counter +82)..(counter +85)].copy_from_slice(&incoming_error_2[..]);
 
-        let extension = rust_jarm::extract_extension_info(input_packet, counter);
+        let extension = rust_jarm::extract_extension_info(input_packet, counter).unwrap();
 
         assert_eq!(extension, expected_result);
     }
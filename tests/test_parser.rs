@@ -0,0 +1,84 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use proptest::prelude::*;
+    use rust_jarm::{cipher_bytes, extract_extension_info, read_packet, version_byte, Jarm, JarmPart};
+
+    fn server_hello() -> Vec<u8> {
+        hex::decode(crate::common::SERVER_HELLO_HEX).unwrap()
+    }
+
+    proptest! {
+        #[test]
+        fn read_packet_never_panics(data in proptest::collection::vec(any::<u8>(), 0..2048)) {
+            let _ = read_packet(data);
+        }
+
+        #[test]
+        fn read_packet_handshake_never_panics(
+            header in (any::<u8>(), any::<u8>(), any::<u8>(), any::<u8>()),
+            body in proptest::collection::vec(any::<u8>(), 0..512),
+        ) {
+            // Record header of a server hello so the parsing goes past the first checks
+            let mut data = vec![22, header.0, header.1, header.2, header.3, 2];
+            data.extend(body);
+            let _ = read_packet(data);
+        }
+
+        #[test]
+        fn read_packet_mutated_server_hello_never_panics(
+            mutations in proptest::collection::vec((0..200_usize, any::<u8>()), 1..8),
+            length in 0..300_usize,
+        ) {
+            let mut data = server_hello();
+            for (index, value) in mutations {
+                data[index] = value;
+            }
+            data.truncate(length);
+            let _ = read_packet(data);
+        }
+
+        #[test]
+        fn extract_extension_info_never_panics(
+            data in proptest::collection::vec(any::<u8>(), 0..512),
+            counter in 0..=255_usize,
+        ) {
+            let _ = extract_extension_info(data, counter);
+        }
+
+        #[test]
+        fn version_and_cipher_bytes_never_panic(value in ".{0,6}") {
+            let _ = version_byte(&value);
+            let _ = cipher_bytes(&value);
+        }
+
+        #[test]
+        fn jarm_part_from_str_never_panics(raw in ".{0,64}") {
+            let _ = JarmPart::from_str(&raw);
+        }
+
+        #[test]
+        fn hash_never_panics(
+            parts in proptest::collection::vec((any::<u16>(), any::<u16>(), proptest::option::of("[a-z0-9/.]{1,8}"), proptest::collection::vec(any::<u16>(), 0..8)), 10),
+        ) {
+            let mut jarm = Jarm::default();
+            jarm.parts = parts.into_iter()
+                .map(|(cipher, version, alpn, extensions)| JarmPart::ServerHello { cipher, version, alpn, extensions })
+                .collect();
+            let _ = jarm.hash();
+        }
+
+        #[test]
+        fn jarm_part_round_trip(
+            cipher in any::<u16>(),
+            version in any::<u16>(),
+            alpn in proptest::option::of("[a-z0-9/.|]{1,8}"),
+            extensions in proptest::collection::vec(any::<u16>(), 0..8),
+        ) {
+            let part = JarmPart::ServerHello { cipher, version, alpn, extensions };
+            prop_assert_eq!(JarmPart::from_str(&part.to_string()).unwrap(), part);
+        }
+    }
+}
@@ -0,0 +1,240 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, SocketAddr};
+    use rust_jarm::{build_packet, Jarm, PseudoRng};
+    use rust_jarm::error::JarmError;
+    use rust_jarm::pcap::{read_exchanges, read_jarms};
+    use crate::common::{server_hello, SERVER_HELLO_HASH};
+
+    const LINKTYPE_ETHERNET: u32 = 1;
+    const LINKTYPE_RAW: u32 = 101;
+
+    struct Packet {
+        source: SocketAddr,
+        destination: SocketAddr,
+        sequence: u32,
+        syn: bool,
+        payload: Vec<u8>,
+    }
+
+    fn tcp(packet: &Packet) -> Vec<u8> {
+        let mut segment = Vec::new();
+        segment.extend(packet.source.port().to_be_bytes());
+        segment.extend(packet.destination.port().to_be_bytes());
+        segment.extend(packet.sequence.to_be_bytes());
+        segment.extend(0_u32.to_be_bytes());  // ack
+        segment.push(5 << 4);  // data offset
+        segment.push(if packet.syn { 0x02 } else { 0x18 });
+        segment.extend([0xff, 0xff, 0, 0, 0, 0]);  // window, checksum, urgent pointer
+        segment.extend(&packet.payload);
+        segment
+    }
+
+    fn ip(packet: &Packet) -> Vec<u8> {
+        let segment = tcp(packet);
+        let mut data = Vec::new();
+        match (packet.source.ip(), packet.destination.ip()) {
+            (IpAddr::V4(source), IpAddr::V4(destination)) => {
+                data.extend([0x45, 0]);
+                data.extend(((20 + segment.len()) as u16).to_be_bytes());
+                data.extend([0, 0, 0x40, 0, 64, 6, 0, 0]);  // id, don't fragment, ttl, tcp, checksum
+                data.extend(source.octets());
+                data.extend(destination.octets());
+            }
+            (IpAddr::V6(source), IpAddr::V6(destination)) => {
+                data.extend([0x60, 0, 0, 0]);
+                data.extend((segment.len() as u16).to_be_bytes());
+                data.extend([6, 64]);  // tcp, hop limit
+                data.extend(source.octets());
+                data.extend(destination.octets());
+            }
+            _ => panic!("mixed ip versions"),
+        }
+        data.extend(segment);
+        data
+    }
+
+    fn frame(packet: &Packet, link_type: u32) -> Vec<u8> {
+        match link_type {
+            LINKTYPE_ETHERNET => {
+                let mut data = vec![0x02; 12];  // mac addresses
+                data.extend([0x08, 0x00]);
+                data.extend(ip(packet));
+                data.resize(data.len().max(60), 0);  // ethernet padding
+                data
+            }
+            _ => ip(packet),
+        }
+    }
+
+    fn pcap(packets: &[Packet], link_type: u32) -> Vec<u8> {
+        let mut capture = Vec::new();
+        capture.extend(0xa1b2c3d4_u32.to_le_bytes());
+        capture.extend(2_u16.to_le_bytes());
+        capture.extend(4_u16.to_le_bytes());
+        capture.extend([0; 8]);
+        capture.extend(65535_u32.to_le_bytes());
+        capture.extend(link_type.to_le_bytes());
+        for packet in packets {
+            let data = frame(packet, link_type);
+            capture.extend([0; 8]);  // timestamp
+            capture.extend((data.len() as u32).to_le_bytes());
+            capture.extend((data.len() as u32).to_le_bytes());
+            capture.extend(data);
+        }
+        capture
+    }
+
+    fn pcapng_block(block_type: u32, body: &[u8]) -> Vec<u8> {
+        let mut body = body.to_vec();
+        body.resize(body.len().div_ceil(4) * 4, 0);
+        let length = (body.len() + 12) as u32;
+        let mut block = Vec::new();
+        block.extend(block_type.to_be_bytes());
+        block.extend(length.to_be_bytes());
+        block.extend(body);
+        block.extend(length.to_be_bytes());
+        block
+    }
+
+    fn pcapng(packets: &[Packet], link_type: u32) -> Vec<u8> {
+        // Big endian to also exercise the byte order detection
+        let mut section_header = 0x1a2b3c4d_u32.to_be_bytes().to_vec();
+        section_header.extend([0, 1, 0, 0]);
+        section_header.extend([0xff; 8]);  // unknown section length
+        let mut interface = (link_type as u16).to_be_bytes().to_vec();
+        interface.extend([0, 0, 0, 0, 0xff, 0xff]);
+
+        let mut capture = pcapng_block(0x0a0d0d0a, &section_header);
+        capture.extend(pcapng_block(1, &interface));
+        for packet in packets {
+            let data = frame(packet, link_type);
+            let mut body = vec![0; 12];  // interface 0, timestamp
+            body.extend((data.len() as u32).to_be_bytes());
+            body.extend((data.len() as u32).to_be_bytes());
+            body.extend(data);
+            capture.extend(pcapng_block(6, &body));
+        }
+        capture
+    }
+
+    /// Packets of a full jarm scan of `server`, each server hello is split over two segments sent out of order
+    fn jarm_scan_packets(client: IpAddr, server: SocketAddr, response: &[u8]) -> Vec<Packet> {
+        let jarm = Jarm::new(server.ip().to_string(), server.port().to_string());
+        let mut packets = Vec::new();
+        for (index, spec) in jarm.queue.iter().enumerate() {
+            let client = SocketAddr::new(client, 40000 + index as u16);
+            let client_hello = build_packet(spec, &PseudoRng {});
+            let (first, second) = response.split_at(100);
+            packets.push(Packet { source: client, destination: server, sequence: 1000, syn: true, payload: vec![] });
+            packets.push(Packet { source: server, destination: client, sequence: 5000, syn: true, payload: vec![] });
+            packets.push(Packet { source: client, destination: server, sequence: 1001, syn: false, payload: client_hello });
+            packets.push(Packet { source: server, destination: client, sequence: 5101, syn: false, payload: second.to_vec() });
+            packets.push(Packet { source: server, destination: client, sequence: 5001, syn: false, payload: first.to_vec() });
+            packets.push(Packet { source: server, destination: client, sequence: 5001, syn: false, payload: first.to_vec() });
+        }
+        packets
+    }
+
+    fn server() -> SocketAddr {
+        "10.0.0.1:443".parse().unwrap()
+    }
+
+    #[test]
+    fn test_read_jarms_pcap() {
+        let packets = jarm_scan_packets("10.0.0.2".parse().unwrap(), server(), &server_hello());
+
+        let jarms = read_jarms(&pcap(&packets, LINKTYPE_ETHERNET)).unwrap();
+
+        assert_eq!(jarms.len(), 1);
+        assert_eq!(jarms[0].server, server());
+        assert_eq!(jarms.into_iter().next().unwrap().hash().unwrap().unwrap(), SERVER_HELLO_HASH);
+    }
+
+    #[test]
+    fn test_read_jarms_pcapng() {
+        let packets = jarm_scan_packets("10.0.0.2".parse().unwrap(), server(), &server_hello());
+
+        let jarms = read_jarms(&pcapng(&packets, LINKTYPE_ETHERNET)).unwrap();
+
+        assert_eq!(jarms.len(), 1);
+        assert_eq!(jarms.into_iter().next().unwrap().hash().unwrap().unwrap(), SERVER_HELLO_HASH);
+    }
+
+    #[test]
+    fn test_read_jarms_ipv6_raw() {
+        let server: SocketAddr = "[2001:db8::1]:8443".parse().unwrap();
+        let packets = jarm_scan_packets("2001:db8::2".parse().unwrap(), server, &server_hello());
+
+        let jarms = read_jarms(&pcap(&packets, LINKTYPE_RAW)).unwrap();
+
+        assert_eq!(jarms.len(), 1);
+        assert_eq!(jarms[0].server, server);
+        assert_eq!(jarms.into_iter().next().unwrap().hash().unwrap().unwrap(), SERVER_HELLO_HASH);
+    }
+
+    #[test]
+    fn test_read_exchanges_identifies_probes() {
+        let packets = jarm_scan_packets("10.0.0.2".parse().unwrap(), server(), &server_hello());
+
+        let exchanges = read_exchanges(&pcap(&packets, LINKTYPE_ETHERNET)).unwrap();
+
+        let probes: Vec<Option<usize>> = exchanges.iter().map(|e| e.probe).collect();
+        assert_eq!(probes, (0..10).map(Some).collect::<Vec<_>>());
+        assert!(exchanges.iter().all(|e| e.part.to_string() == "c02b|0303|h2|0000-0017-ff01-000b-0023-0010"));
+    }
+
+    #[test]
+    fn test_read_jarms_incomplete_scan() {
+        let mut packets = jarm_scan_packets("10.0.0.2".parse().unwrap(), server(), &server_hello());
+        packets.truncate(6 * 9);  // last probe missing
+
+        let jarms = read_jarms(&pcap(&packets, LINKTYPE_ETHERNET)).unwrap();
+
+        assert_eq!(jarms.len(), 1);
+        assert!(jarms[0].parts[9].is_none());
+        assert!(jarms.into_iter().next().unwrap().hash().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_read_jarms_no_response() {
+        let packets: Vec<Packet> = jarm_scan_packets("10.0.0.2".parse().unwrap(), server(), &[0; 200]).into_iter()
+            .filter(|p| p.source != server())
+            .collect();
+
+        let jarms = read_jarms(&pcap(&packets, LINKTYPE_ETHERNET)).unwrap();
+
+        assert_eq!(jarms.into_iter().next().unwrap().hash().unwrap().unwrap(), "0".repeat(62));
+    }
+
+    #[test]
+    fn test_read_jarms_two_servers() {
+        let other_server: SocketAddr = "10.0.0.3:443".parse().unwrap();
+        let mut packets = jarm_scan_packets("10.0.0.2".parse().unwrap(), server(), &server_hello());
+        packets.extend(jarm_scan_packets("10.0.0.2".parse().unwrap(), other_server, &server_hello()));
+
+        let jarms = read_jarms(&pcap(&packets, LINKTYPE_ETHERNET)).unwrap();
+
+        let servers: Vec<SocketAddr> = jarms.iter().map(|j| j.server).collect();
+        assert_eq!(servers, vec![server(), other_server]);
+    }
+
+    #[test]
+    fn test_read_jarms_invalid_capture() {
+        let error = read_jarms(b"not a capture").err().unwrap();
+
+        assert!(matches!(error, JarmError::Capture(_)));
+    }
+
+    #[test]
+    fn test_read_jarms_truncated_capture() {
+        let packets = jarm_scan_packets("10.0.0.2".parse().unwrap(), server(), &server_hello());
+        let capture = pcap(&packets, LINKTYPE_ETHERNET);
+
+        let error = read_jarms(&capture[..capture.len() - 10]).err().unwrap();
+
+        assert!(matches!(error, JarmError::Capture(_)));
+    }
+}
@@ -0,0 +1,163 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::thread;
+    use std::time::{Duration, Instant};
+    use rstest::*;
+    use rust_jarm::error::JarmError;
+    use rust_jarm::{Jarm, JarmPart};
+    use crate::common::{local_jarm, read_client_hello, server_hello, spawn_server, SERVER_HELLO_HASH};
+
+    const ALERT: [u8; 7] = [0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 0x28];
+
+    /// Jarm sending only the first probe
+    fn single_probe_jarm(port: u16) -> Jarm {
+        let mut jarm = local_jarm(port);
+        jarm.queue.truncate(1);
+        jarm
+    }
+
+    /// First record of the fixture, only holding the server hello
+    fn server_hello_record() -> Vec<u8> {
+        server_hello()[..81].to_vec()
+    }
+
+    /// Block until the client closes the connection, so the client can't rely on it to stop reading
+    fn wait_for_close(stream: &mut TcpStream) {
+        let _ = stream.read(&mut [0_u8; 1]);
+    }
+
+    fn send_in_chunks(mut stream: TcpStream, chunks: &[Vec<u8>]) {
+        stream.set_nodelay(true).unwrap();
+        read_client_hello(&mut stream);
+        for chunk in chunks {
+            stream.write_all(chunk).unwrap();
+            stream.flush().unwrap();
+            thread::sleep(Duration::from_millis(5));
+        }
+        wait_for_close(&mut stream);
+    }
+
+    #[test]
+    fn test_response_sent_byte_by_byte() {
+        let address = spawn_server(|stream| {
+            let bytes: Vec<Vec<u8>> = server_hello_record().into_iter().map(|b| vec![b]).collect();
+            send_in_chunks(stream, &bytes);
+        });
+
+        let hash = local_jarm(address.port()).hash().unwrap();
+
+        assert_eq!(hash, SERVER_HELLO_HASH);
+    }
+
+    #[test]
+    fn test_server_hello_spanning_two_records() {
+        let address = spawn_server(|stream| {
+            let handshake = &server_hello_record()[5..];
+            let (first, second) = handshake.split_at(30);
+            let mut first_record = vec![0x16, 0x03, 0x03, 0x00, first.len() as u8];
+            first_record.extend(first);
+            let mut second_record = vec![0x16, 0x03, 0x03, 0x00, second.len() as u8];
+            second_record.extend(second);
+            send_in_chunks(stream, &[first_record, second_record]);
+        });
+
+        let hash = local_jarm(address.port()).hash().unwrap();
+
+        assert_eq!(hash, SERVER_HELLO_HASH);
+    }
+
+    #[test]
+    fn test_several_handshake_messages_in_one_record() {
+        let address = spawn_server(|stream| {
+            let mut handshake = server_hello_record()[5..].to_vec();
+            handshake.extend([0x0e, 0x00, 0x00, 0x00]);  // server hello done
+            let mut record = vec![0x16, 0x03, 0x03, 0x00, handshake.len() as u8];
+            record.extend(handshake);
+            send_in_chunks(stream, &[record]);
+        });
+
+        let hash = local_jarm(address.port()).hash().unwrap();
+
+        assert_eq!(hash, SERVER_HELLO_HASH);
+    }
+
+    #[test]
+    fn test_alert_stops_reading() {
+        let address = spawn_server(|stream| send_in_chunks(stream, &[ALERT.to_vec()]));
+        let mut jarm = single_probe_jarm(address.port());
+
+        let start = Instant::now();
+        let parts = jarm.retrieve_parts().unwrap();
+
+        assert_eq!(parts, vec![JarmPart::NoResponse]);
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_incomplete_response_respects_timeout() {
+        let address = spawn_server(|stream| send_in_chunks(stream, &[server_hello_record()[..40].to_vec()]));
+        let mut jarm = single_probe_jarm(address.port());
+        jarm.timeout = Duration::from_millis(300);
+
+        let start = Instant::now();
+        let report = jarm.report();
+
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert_eq!(report.probes[0].part, JarmPart::NoResponse);
+        assert!(matches!(report.probes[0].error, Some(JarmError::Parse { offset: 40, reason: "incomplete TLS record" })));
+    }
+
+    #[test]
+    fn test_handshake_record_without_server_hello_stops_reading() {
+        let address = spawn_server(|stream| {
+            let certificate = [0x0b, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00];
+            let mut record = vec![0x16, 0x03, 0x03, 0x00, certificate.len() as u8];
+            record.extend(certificate);
+            send_in_chunks(stream, &[record]);
+        });
+        let mut jarm = single_probe_jarm(address.port());
+
+        let start = Instant::now();
+        let parts = jarm.retrieve_parts().unwrap();
+
+        assert_eq!(parts, vec![JarmPart::NoResponse]);
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_endless_server_hello_stops_reading() {
+        let address = spawn_server(|stream| {
+            // A server hello claiming 16 MB, in a record of the maximum length, followed by more data
+            let mut record = vec![0x16, 0x03, 0x03, 0xff, 0xff, 0x02, 0xff, 0xff, 0xff];
+            record.resize(1 << 17, 0);
+            send_in_chunks(stream, &[record]);
+        });
+        let mut jarm = single_probe_jarm(address.port());
+        jarm.timeout = Duration::from_secs(10);
+
+        let start = Instant::now();
+        let report = jarm.report();
+
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert!(report.probes[0].bytes_received < 1 << 15);
+        assert!(matches!(report.probes[0].error, Some(JarmError::Parse { reason: "server hello longer than a TLS record", .. })));
+    }
+
+    #[rstest]
+    #[case(40)]
+    #[case(60)]
+    fn test_truncated_server_hello_is_not_parsed(#[case] length: usize) {
+        let address = spawn_server(move |mut stream| {
+            read_client_hello(&mut stream);
+            stream.write_all(&server_hello_record()[..length]).unwrap();  // then close
+        });
+
+        let error = single_probe_jarm(address.port()).retrieve_parts().err().unwrap();
+
+        assert!(matches!(error, JarmError::Parse { reason: "incomplete TLS record", .. }));
+    }
+}
@@ -0,0 +1,130 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::{Duration, Instant};
+    use rust_jarm::alert::TlsAlert;
+    use rust_jarm::error::JarmError;
+    use rust_jarm::report::ProbeOutcome;
+    use rust_jarm::{Jarm, JarmPart, TestRng};
+    use crate::common::{closed_port, local_jarm, read_client_hello, server_hello, spawn_server, spawn_tls_server, SERVER_HELLO_HASH};
+
+    #[test]
+    fn test_report_server_hello() {
+        let address = spawn_tls_server(server_hello());
+
+        let report = local_jarm(address.port()).report();
+
+        assert_eq!(report.probes.len(), 10);
+        for probe in &report.probes {
+            assert_eq!(probe.address, Some(address));
+            assert_eq!(probe.outcome, ProbeOutcome::ServerHello);
+            assert_eq!(probe.bytes_received, server_hello().len());
+            assert!(probe.connect_time.is_some());
+            assert!(probe.time_to_first_byte.is_some());
+            assert!(probe.error.is_none());
+        }
+        assert_eq!(report.hash().unwrap(), SERVER_HELLO_HASH);
+    }
+
+    #[test]
+    fn test_report_outcomes() {
+        let connections = Arc::new(AtomicUsize::new(0));
+        let address = spawn_server(move |mut stream| {
+            match connections.fetch_add(1, Ordering::SeqCst) {
+                0 => {
+                    read_client_hello(&mut stream);
+                    stream.write_all(&[0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 0x46]).unwrap();
+                },
+                1 => {
+                    read_client_hello(&mut stream);  // then close without answering
+                },
+                2 => {
+                    stream.peek(&mut [0_u8; 1]).unwrap();  // closing with unread data resets the connection
+                },
+                3 => {
+                    read_client_hello(&mut stream);
+                    let _ = stream.read(&mut [0_u8; 1]);  // wait for the client to give up
+                },
+                _ => {
+                    read_client_hello(&mut stream);
+                    stream.write_all(&server_hello()).unwrap();
+                },
+            }
+        });
+
+        let mut jarm = local_jarm(address.port());
+        jarm.timeout = Duration::from_millis(500);  // the fourth probe times out
+        let report = jarm.report();
+
+        let outcomes: Vec<ProbeOutcome> = report.probes.iter().map(|p| p.outcome).collect();
+        assert_eq!(outcomes[..5], [
+            ProbeOutcome::Alert(TlsAlert { level: 2, description: 70 }),
+            ProbeOutcome::Closed,
+            ProbeOutcome::Reset,
+            ProbeOutcome::Timeout,
+            ProbeOutcome::ServerHello,
+        ]);
+        assert!(report.probes[..2].iter().all(|p| p.error.is_none() && p.part == JarmPart::NoResponse));
+        assert!(report.probes[2..4].iter().all(|p| matches!(p.error, Some(JarmError::Io(_)))));
+        assert_eq!(report.probes[3].time_to_first_byte, None);
+        assert!(matches!(report.hash(), Err(JarmError::Io(_))));
+    }
+
+    #[test]
+    fn test_report_scan_timeout() {
+        let address = spawn_server(|mut stream| {
+            read_client_hello(&mut stream);
+            let _ = stream.read(&mut [0_u8; 1]);  // wait for the client to give up
+        });
+
+        let mut jarm = local_jarm(address.port());
+        jarm.timeout = Duration::from_millis(400);
+        jarm.scan_timeout = Some(Duration::from_secs(1));
+        let start = Instant::now();
+        let report = jarm.report();
+
+        // Ten probes timing out one after the other would take 4 seconds
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert_eq!(report.probes.len(), 10);
+        assert!(report.probes.iter().all(|p| p.outcome == ProbeOutcome::Timeout));
+    }
+
+    #[test]
+    fn test_report_keeps_going_after_failures() {
+        let report = local_jarm(closed_port()).report();
+
+        assert_eq!(report.probes.len(), 10);
+        assert!(report.probes.iter().all(|p| p.outcome == ProbeOutcome::Reset && p.connect_time.is_none()));
+        assert!(matches!(report.into_parts(), Err(JarmError::Connection(_))));
+    }
+
+    #[test]
+    fn test_report_unresolved_name() {
+        let report = Jarm::new("invalid.invalid".to_string(), "443".to_string()).report();
+
+        assert!(report.probes.iter().all(|p| p.outcome == ProbeOutcome::Failed && p.address.is_none()));
+        assert!(matches!(report.probes[0].error, Some(JarmError::DnsResolve { .. })));
+    }
+
+    #[test]
+    fn test_report_explicit_address_keeps_sni() {
+        let address = spawn_server(|mut stream| {
+            let client_hello = read_client_hello(&mut stream);
+            if client_hello.windows(12).any(|w| w == b"jarm.example") {
+                stream.write_all(&server_hello()).unwrap();
+            }
+        });
+        let mut jarm = Jarm::new("jarm.example".to_string(), address.port().to_string());
+        jarm.rng = Box::new(TestRng {});
+        jarm.address = Some(address);
+
+        let report = jarm.report();
+
+        assert_eq!(report.address, Some(address));
+        assert_eq!(report.hash().unwrap(), SERVER_HELLO_HASH);
+    }
+}
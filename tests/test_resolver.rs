@@ -0,0 +1,90 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, SocketAddr};
+    use std::sync::Arc;
+    use rust_jarm::error::JarmError;
+    use rust_jarm::resolver::{JarmResolver, StaticResolver, SystemResolver};
+    use rust_jarm::{Jarm, TestRng};
+    use crate::common::{server_hello, spawn_tls_server, SERVER_HELLO_HASH};
+
+    #[test]
+    fn test_static_resolver() {
+        let resolver = StaticResolver::new()
+            .with_host("jarm.example", [10, 0, 0, 1].into())
+            .with_host("jarm.example", "2001:db8::1".parse().unwrap());
+
+        let addresses = resolver.resolve("jarm.example", 443).unwrap();
+
+        assert_eq!(addresses, vec![
+            "10.0.0.1:443".parse::<SocketAddr>().unwrap(),
+            "[2001:db8::1]:443".parse::<SocketAddr>().unwrap(),
+        ]);
+    }
+
+    #[test]
+    fn test_static_resolver_ip_literal() {
+        let addresses = StaticResolver::new().resolve("2001:db8::1", 8443).unwrap();
+
+        assert_eq!(addresses, vec!["[2001:db8::1]:8443".parse::<SocketAddr>().unwrap()]);
+    }
+
+    #[test]
+    fn test_static_resolver_unknown_host() {
+        let error = StaticResolver::new().resolve("unknown.example", 443).err().unwrap();
+
+        if let JarmError::DnsResolve { name, error } = error {
+            assert_eq!(name, "unknown.example");
+            assert_eq!(error.underlying_error.unwrap().to_string(), "unknown host");
+        } else { panic!("unexpected type") }
+    }
+
+    #[test]
+    fn test_system_resolver_ip_literal() {
+        let addresses = SystemResolver.resolve("127.0.0.1", 443).unwrap();
+
+        assert_eq!(addresses, vec!["127.0.0.1:443".parse::<SocketAddr>().unwrap()]);
+    }
+
+    #[test]
+    fn test_hash_with_static_resolver() {
+        let address = spawn_tls_server(server_hello());
+        let mut jarm = Jarm::new("jarm.example".to_string(), address.port().to_string());
+        jarm.rng = Box::new(TestRng {});
+        jarm.resolver = Arc::new(StaticResolver::new().with_host("jarm.example", address.ip()));
+
+        assert_eq!(jarm.hash().unwrap(), SERVER_HELLO_HASH);
+    }
+
+    #[test]
+    fn test_hash_dns_error_carries_name() {
+        let mut jarm = Jarm::new("unknown.example".to_string(), "443".to_string());
+        jarm.resolver = Arc::new(StaticResolver::new());
+
+        let error = jarm.hash().err().unwrap();
+
+        assert!(matches!(error, JarmError::DnsResolve { name, .. } if name == "unknown.example"));
+    }
+
+    #[test]
+    fn test_hash_invalid_port() {
+        let mut jarm = Jarm::new("127.0.0.1".to_string(), "not_a_port".to_string());
+
+        let error = jarm.hash().err().unwrap();
+
+        assert!(matches!(error, JarmError::DnsResolve { name, .. } if name == "127.0.0.1"));
+    }
+
+    #[test]
+    fn test_resolver_shared_by_scanner() {
+        let address = spawn_tls_server(server_hello());
+        let ip: IpAddr = address.ip();
+        let mut scanner = rust_jarm::scanner::Scanner::new(2);
+        scanner.resolver = Arc::new(StaticResolver::new().with_host("jarm.example", ip));
+
+        let results: Vec<_> = scanner.scan(vec![("jarm.example".to_string(), address.port().to_string())]).collect();
+
+        assert_eq!(results[0].1.as_ref().unwrap(), SERVER_HELLO_HASH);
+    }
+}
@@ -0,0 +1,133 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+    use rust_jarm::error::JarmError;
+    use rust_jarm::expand::TargetExpander;
+    use rust_jarm::scanner::Scanner;
+    use rust_jarm::target::Target;
+    use rust_jarm::FailurePolicy;
+    use crate::common::{closed_port, read_client_hello, server_hello, spawn_server, spawn_tls_server, SERVER_HELLO_HASH};
+
+    fn target(port: u16) -> (String, String) {
+        ("127.0.0.1".to_string(), port.to_string())
+    }
+
+    #[test]
+    fn test_scan_yields_every_target() {
+        let first = spawn_tls_server(server_hello());
+        let second = spawn_tls_server(server_hello());
+        let unreachable = closed_port();
+        let targets = vec![target(first.port()), target(second.port()), target(unreachable)];
+
+        let mut scanner = Scanner::new(2);
+        scanner.timeout = Duration::from_secs(2);
+        let results: HashMap<(String, String), Result<String, JarmError>> = scanner.scan(targets).collect();
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[&target(first.port())].as_ref().unwrap(), SERVER_HELLO_HASH);
+        assert_eq!(results[&target(second.port())].as_ref().unwrap(), SERVER_HELLO_HASH);
+        assert!(matches!(results[&target(unreachable)], Err(JarmError::Connection(_))));
+    }
+
+    #[test]
+    fn test_scan_respects_workers_limit() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let max_running = Arc::new(AtomicUsize::new(0));
+        let (running_clone, max_running_clone) = (Arc::clone(&running), Arc::clone(&max_running));
+        let address = spawn_server(move |mut stream| {
+            read_client_hello(&mut stream);
+            let now_running = running_clone.fetch_add(1, Ordering::SeqCst) + 1;
+            max_running_clone.fetch_max(now_running, Ordering::SeqCst);
+            std::thread::sleep(Duration::from_millis(5));
+            running_clone.fetch_sub(1, Ordering::SeqCst);
+            std::io::Write::write_all(&mut stream, &server_hello()).unwrap();
+        });
+        let targets: Vec<(String, String)> = (0..6).map(|_| target(address.port())).collect();
+
+        let results: Vec<_> = Scanner::new(2).scan(targets).collect();
+
+        assert_eq!(results.len(), 6);
+        assert!(results.iter().all(|(_, result)| result.is_ok()));
+        // A target only opens one connection at a time
+        assert!(max_running.load(Ordering::SeqCst) <= 2);
+    }
+
+    #[test]
+    fn test_scan_rate_limit() {
+        let address = spawn_tls_server(server_hello());
+        let targets: Vec<(String, String)> = (0..3).map(|_| target(address.port())).collect();
+
+        let mut scanner = Scanner::new(3);
+        scanner.rate_limit = Some(10);  // one target every 100ms
+        let start = Instant::now();
+        let results: Vec<_> = scanner.scan(targets).collect();
+
+        assert_eq!(results.len(), 3);
+        assert!(start.elapsed() >= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn test_scan_timeout_covers_every_probe() {
+        let address = spawn_server(|mut stream| {
+            read_client_hello(&mut stream);
+            let _ = std::io::Read::read(&mut stream, &mut [0_u8; 1]);  // wait for the client to give up
+        });
+
+        let mut scanner = Scanner::new(1);
+        scanner.timeout = Duration::from_millis(500);
+        scanner.failure_policy = FailurePolicy::Reference;  // every probe is sent despite the timeouts
+        let start = Instant::now();
+        let results: Vec<_> = scanner.scan(vec![target(address.port())]).collect();
+
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert_eq!(results[0].1.as_ref().unwrap(), &"0".repeat(62));
+    }
+
+    #[test]
+    fn test_scan_targets() {
+        let address = spawn_tls_server(server_hello());
+        let unreachable = Target::new("127.0.0.1", closed_port()).unwrap();
+        let targets = vec![Target::new("127.0.0.1", address.port()).unwrap(), unreachable.clone()];
+
+        let mut scanner = Scanner::new(2);
+        scanner.timeout = Duration::from_secs(2);
+        let results: Vec<(Target, Result<String, JarmError>)> = scanner.scan(targets).collect();
+
+        assert_eq!(results.len(), 2);
+        for (target, result) in results {
+            match target == unreachable {
+                true => assert!(matches!(result, Err(JarmError::Connection(_)))),
+                false => assert_eq!(result.unwrap(), SERVER_HELLO_HASH),
+            }
+        }
+    }
+
+    #[test]
+    fn test_scan_expanded_targets() {
+        let address = spawn_tls_server(server_hello());
+        let patterns = vec![format!("127.0.0.1:{}", address.port()), format!("127.0.0.2/31:{}", address.port())];
+        let targets = TargetExpander::new().expand(patterns).map(Result::unwrap);
+
+        let results: Vec<_> = Scanner::default().scan(targets).collect();
+
+        assert_eq!(results.len(), 3);
+        let (target, result) = results.iter().find(|(target, _)| target.to_string() == address.to_string()).unwrap();
+        assert_eq!(target.port, address.port());
+        assert_eq!(result.as_ref().unwrap(), SERVER_HELLO_HASH);
+    }
+
+    #[test]
+    fn test_scan_lazy_iterator() {
+        let address = spawn_tls_server(server_hello());
+        let targets = (0..4).map(move |_| target(address.port()));
+
+        let results: Vec<_> = Scanner::default().scan(targets).collect();
+
+        assert_eq!(results.len(), 4);
+    }
+}
@@ -0,0 +1,88 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use rstest::*;
+    use rust_jarm::hash::JarmHash;
+    use rust_jarm::similarity::{compare_hashes, compare_parts, ProbeComparison};
+    use rust_jarm::JarmPart;
+
+    const GOOGLE_HASH: &str = "27d40d40d29d40d1dc42d43d00041d4689ee210389f4f6b4b5b1b93f92252d";
+
+    fn hash(hash: &str) -> JarmHash {
+        hash.parse().unwrap()
+    }
+
+    fn parts(raw: &[(usize, &str)]) -> Vec<JarmPart> {
+        let mut parts = vec![JarmPart::NoResponse; 10];
+        for (index, part) in raw {
+            parts[*index] = part.parse().unwrap();
+        }
+        parts
+    }
+
+    fn assert_score(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "score {actual} instead of {expected}");
+    }
+
+    #[rstest]
+    // Identical
+    #[case(GOOGLE_HASH, 1.0, 10, true)]
+    // Only the cipher of the first probe differs
+    #[case("28d40d40d29d40d1dc42d43d00041d4689ee210389f4f6b4b5b1b93f92252d", 0.96, 9, true)]
+    // The first probe got no response
+    #[case("00040d40d29d40d1dc42d43d00041d4689ee210389f4f6b4b5b1b93f92252d", 0.92, 9, true)]
+    // Only the extensions digest differs
+    #[case("27d40d40d29d40d1dc42d43d00041d00000000000000000000000000000000", 0.8, 10, false)]
+    // Nothing in common
+    #[case("2ae2ae2ae2ae2ae2ae2ae2ae2ae2ae00000000000000000000000000000001", 0.0, 0, false)]
+    fn test_compare_hashes(#[case] other: &str, #[case] score: f64, #[case] matching_probes: usize, #[case] extensions_digest: bool) {
+        let similarity = compare_hashes(&hash(GOOGLE_HASH), &hash(other));
+
+        assert_score(similarity.score(), score);
+        assert_eq!(similarity.matching_probes(), matching_probes);
+        assert_eq!(similarity.extensions_digest, extensions_digest);
+        assert!(similarity.probes.iter().all(|probe| probe.extensions.is_none()));
+    }
+
+    #[test]
+    fn test_compare_no_tls_hashes() {
+        let similarity = compare_hashes(&JarmHash::no_tls(), &JarmHash::no_tls());
+
+        assert_score(similarity.score(), 1.0);
+    }
+
+    #[test]
+    fn test_compare_is_symmetric() {
+        let (a, b) = (hash(GOOGLE_HASH), hash("28d40d40d29d40d1dc42d43d00041d4689ee210389f4f6b4b5b1b93f92252d"));
+
+        assert_eq!(compare_hashes(&a, &b), compare_hashes(&b, &a));
+    }
+
+    #[test]
+    fn test_compare_parts() {
+        let a = parts(&[(0, "c02f|0303|h2|ff01-0000-0010"), (1, "c030|0303|h2|ff01-0000-0010"), (2, "c02f|0303||ff01")]);
+        let b = parts(&[(0, "c02f|0303|h2|ff01-0000-0010"), (1, "c030|0303|http/1.1|ff01-0000-0010"), (3, "c02f|0303||ff01")]);
+
+        let similarity = compare_parts(&a, &b);
+
+        assert_eq!(similarity.probes[0], ProbeComparison { cipher: true, version: true, extensions: Some(true) });
+        assert_eq!(similarity.probes[1], ProbeComparison { cipher: true, version: true, extensions: Some(false) });
+        assert_eq!(similarity.probes[2], ProbeComparison { cipher: false, version: false, extensions: Some(false) });
+        assert_eq!(similarity.probes[4], ProbeComparison { cipher: true, version: true, extensions: Some(true) });
+        assert_eq!(similarity.matching_probes(), 7);
+        assert!(!similarity.extensions_digest);
+        // 8 probes with the same cipher and version, 7 with the same ALPN and extensions
+        assert_score(similarity.score(), 8.0 * 0.08 + 0.2 * 0.7);
+    }
+
+    #[test]
+    fn test_compare_missing_parts() {
+        let a = parts(&[(0, "c02f|0303|h2|ff01-0000-0010")]);
+
+        let similarity = compare_parts(&a[..1], &a);
+
+        assert_score(similarity.score(), 1.0);
+        assert!(similarity.extensions_digest);
+    }
+}
@@ -0,0 +1,98 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use rstest::*;
+    use rust_jarm::error::JarmError;
+    use rust_jarm::sni::{domain_to_ascii, ServerName};
+    use rust_jarm::{extension_server_name, Jarm, TestRng};
+    use crate::common::{server_hello, CannedTransport, SERVER_HELLO_HASH};
+
+    fn sni(host: &str, server_name: ServerName) -> Vec<u8> {
+        let mut jarm = Jarm::new(host.to_string(), "443".to_string());
+        jarm.set_server_name(server_name).unwrap();
+        extension_server_name(&jarm.queue[0])
+    }
+
+    #[test]
+    fn test_host_as_server_name() {
+        assert_eq!(sni("127.0.0.1", ServerName::Host), b"\x00\x00\x00\x0e\x00\x0c\x00\x00\x09127.0.0.1".to_vec());
+    }
+
+    #[rstest]
+    #[case("127.0.0.1")]
+    #[case("2001:db8::1")]
+    fn test_no_server_name_for_ip_literal(#[case] host: &str) {
+        assert!(sni(host, ServerName::HostName).is_empty());
+    }
+
+    #[test]
+    fn test_host_name_as_server_name() {
+        assert_eq!(sni("example.com", ServerName::HostName), sni("example.com", ServerName::Host));
+    }
+
+    #[test]
+    fn test_chosen_server_name() {
+        assert_eq!(sni("192.0.2.1", ServerName::Name("example.com".to_string())), b"\x00\x00\x00\x10\x00\x0e\x00\x00\x0bexample.com".to_vec());
+    }
+
+    #[test]
+    fn test_omitted_server_name() {
+        assert!(sni("example.com", ServerName::Omitted).is_empty());
+    }
+
+    #[test]
+    fn test_idn_server_name() {
+        assert_eq!(sni("münchen.de", ServerName::Host), b"\x00\x00\x00\x16\x00\x14\x00\x00\x11xn--mnchen-3ya.de".to_vec());
+    }
+
+    #[rstest]
+    #[case("", "empty host")]
+    #[case("foo bar", "invalid character in host name")]
+    #[case(&"a".repeat(70_000), "host name longer than 253 bytes")]
+    fn test_invalid_chosen_server_name(#[case] name: &str, #[case] expected: &str) {
+        let mut jarm = Jarm::new("192.0.2.1".to_string(), "443".to_string());
+
+        let error = jarm.set_server_name(ServerName::Name(name.to_string())).err().unwrap();
+
+        assert!(matches!(error, JarmError::InvalidTarget { reason, .. } if reason == expected));
+        assert!(jarm.queue.iter().all(|spec| spec.server_name == ServerName::Host));
+    }
+
+    #[rstest]
+    #[case("example.com", "example.com")]
+    #[case("Example.COM", "Example.COM")]  // ASCII names are kept as is
+    #[case("MÜNCHEN.de", "xn--mnchen-3ya.de")]
+    #[case("пример。испытание", "xn--e1afmkfd.xn--80akhbyknj4f")]
+    fn test_domain_to_ascii(#[case] domain: &str, #[case] expected: &str) {
+        assert_eq!(domain_to_ascii(domain).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_domain_to_ascii_overflow() {
+        let domain = format!("{}\u{10ffff}.example", "a".repeat(4000));
+
+        let error = domain_to_ascii(&domain).err().unwrap();
+
+        assert!(matches!(error, JarmError::InvalidTarget { reason: "label too long to be punycode encoded", .. }));
+    }
+
+    #[test]
+    fn test_scan_ip_with_chosen_server_name() {
+        let transport = Arc::new(CannedTransport::new(server_hello()));
+        let mut jarm = Jarm::new("192.0.2.1".to_string(), "443".to_string());
+        jarm.rng = Box::new(TestRng {});
+        jarm.transport = transport.clone();
+        jarm.set_server_name(ServerName::Name("origin.example".to_string())).unwrap();
+
+        assert_eq!(jarm.hash().unwrap(), SERVER_HELLO_HASH);
+        let client_hellos = transport.client_hellos.lock().unwrap();
+        assert_eq!(client_hellos.len(), 10);
+        for (address, client_hello) in client_hellos.iter() {
+            assert_eq!(*address, "192.0.2.1:443".parse().unwrap());
+            assert!(client_hello.windows(14).any(|window| window == b"origin.example"));
+            assert!(!client_hello.windows(9).any(|window| window == b"192.0.2.1"));
+        }
+    }
+}
@@ -0,0 +1,119 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+    use std::sync::Arc;
+    use rstest::*;
+    use rust_jarm::error::JarmError;
+    use rust_jarm::sni::ServerName;
+    use rust_jarm::target::{Target, TargetHost};
+    use rust_jarm::{Jarm, TestRng};
+    use crate::common::{server_hello, CannedTransport, SERVER_HELLO_HASH};
+
+    fn name(name: &str) -> TargetHost {
+        TargetHost::Name(name.to_string())
+    }
+
+    fn ip(ip: &str) -> TargetHost {
+        TargetHost::Ip(ip.parse::<IpAddr>().unwrap())
+    }
+
+    #[rstest]
+    #[case("google.com", name("google.com"), 443)]
+    #[case("google.com:8443", name("google.com"), 8443)]
+    #[case("8.8.4.4:853", ip("8.8.4.4"), 853)]
+    #[case("[2001:db8::1]:8443", ip("2001:db8::1"), 8443)]
+    #[case("[2001:db8::1]", ip("2001:db8::1"), 443)]
+    #[case("2001:db8::1", ip("2001:db8::1"), 443)]
+    #[case("https://google.com/", name("google.com"), 443)]
+    #[case("https://google.com:8443/path?query#fragment", name("google.com"), 8443)]
+    #[case("HTTPS://[::1]:4433", ip("::1"), 4433)]
+    #[case("münchen.de", name("münchen.de"), 443)]
+    #[case("example.com.:443", name("example.com."), 443)]
+    fn test_parse_target(#[case] value: &str, #[case] host: TargetHost, #[case] port: u16) {
+        let target: Target = value.parse().unwrap();
+
+        assert_eq!(target.host, host);
+        assert_eq!(target.port, port);
+        assert_eq!(target.server_name, ServerName::Host);
+        assert_eq!(target.address, None);
+    }
+
+    #[rstest]
+    #[case("google.com:", "missing port after ':'")]
+    #[case("google.com:https", "the port is not a number between 1 and 65535")]
+    #[case("google.com:65536", "the port is not a number between 1 and 65535")]
+    #[case("google.com:0", "port 0 can't be scanned")]
+    #[case("http://google.com", "only https URLs can be scanned")]
+    #[case("https://user@google.com", "user information is not supported")]
+    #[case("[2001:db8::1:443", "missing closing bracket")]
+    #[case("[google.com]:443", "invalid IPv6 address")]
+    #[case("[::1]443", "unexpected characters after the IPv6 address")]
+    #[case("", "empty host")]
+    #[case(":443", "empty host")]
+    #[case("google..com", "empty label in host name")]
+    #[case("goo gle.com", "invalid character in host name")]
+    fn test_parse_invalid_target(#[case] value: &str, #[case] expected_reason: &str) {
+        let error = value.parse::<Target>().err().unwrap();
+
+        if let JarmError::InvalidTarget { target, reason } = error {
+            assert_eq!(target, value);
+            assert_eq!(reason, expected_reason);
+        } else { panic!("unexpected type") }
+    }
+
+    #[test]
+    fn test_host_name_length() {
+        let label = "a".repeat(63);
+        let longest = [label.as_str(), &label, &label, &"a".repeat(61)].join(".");
+        assert!(Target::new(&longest, 443).is_ok());
+
+        let too_long = format!("a{longest}");
+        assert!(matches!(Target::new(&too_long, 443), Err(JarmError::InvalidTarget { reason: "host name longer than 253 bytes", .. })));
+        let long_label = format!("{}.com", "a".repeat(64));
+        assert!(matches!(Target::new(&long_label, 443), Err(JarmError::InvalidTarget { reason: "label of host name longer than 63 bytes", .. })));
+    }
+
+    #[rstest]
+    #[case("google.com:443", "google.com:443")]
+    #[case("https://[2001:db8::1]/", "[2001:db8::1]:443")]
+    fn test_display_target(#[case] value: &str, #[case] expected: &str) {
+        assert_eq!(value.parse::<Target>().unwrap().to_string(), expected);
+    }
+
+    #[test]
+    fn test_invalid_server_name_of_target() {
+        let error = Target::new("192.0.2.1", 443).unwrap().with_server_name(ServerName::Name(String::new())).err().unwrap();
+
+        assert!(matches!(error, JarmError::InvalidTarget { reason: "empty host", .. }));
+    }
+
+    #[test]
+    fn test_probe_queue_from_target() {
+        let target = Target::new("2001:db8::1", 8443).unwrap()
+            .with_server_name(ServerName::Name("origin.example".to_string())).unwrap();
+
+        let queue = target.probe_queue();
+
+        assert_eq!(queue.len(), 10);
+        for spec in &queue {
+            assert_eq!(spec.host, "2001:db8::1");
+            assert_eq!(spec.port, "8443");
+            assert_eq!(spec.server_name, ServerName::Name("origin.example".to_string()));
+        }
+    }
+
+    #[test]
+    fn test_jarm_from_target() {
+        let transport = Arc::new(CannedTransport::new(server_hello()));
+        let target = "https://jarm.example:8443/".parse::<Target>().unwrap()
+            .with_address("192.0.2.1:8443".parse().unwrap());
+        let mut jarm = Jarm::from(target);
+        jarm.rng = Box::new(TestRng {});
+        jarm.transport = transport.clone();
+
+        assert_eq!(jarm.hash().unwrap(), SERVER_HELLO_HASH);
+        assert!(transport.client_hellos.lock().unwrap().iter().all(|(address, _)| *address == "192.0.2.1:8443".parse().unwrap()));
+    }
+}
@@ -0,0 +1,92 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::net::SocketAddr;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use rust_jarm::error::JarmError;
+    use rust_jarm::report::ProbeOutcome;
+    use rust_jarm::resolver::StaticResolver;
+    use rust_jarm::transport::{JarmStream, JarmTransport};
+    use rust_jarm::{Jarm, JarmPart, TestRng};
+    use crate::common::{server_hello, CannedTransport, SERVER_HELLO_HASH};
+
+    /// Transport refusing every connection
+    struct RefusingTransport;
+
+    impl JarmTransport for RefusingTransport {
+        fn connect(&self, _address: SocketAddr, _timeout: Duration) -> io::Result<Box<dyn JarmStream>> {
+            Err(io::Error::from(io::ErrorKind::ConnectionRefused))
+        }
+    }
+
+    fn canned_jarm(transport: Arc<CannedTransport>, resolver: StaticResolver) -> Jarm {
+        let mut jarm = Jarm::new("jarm.example".to_string(), "443".to_string());
+        jarm.rng = Box::new(TestRng {});
+        jarm.resolver = Arc::new(resolver);
+        jarm.transport = transport;
+        jarm
+    }
+
+    #[test]
+    fn test_retrieve_parts_over_transport() {
+        let transport = Arc::new(CannedTransport::new(server_hello()));
+        let mut jarm = canned_jarm(transport.clone(), StaticResolver::new().with_host("jarm.example", [192, 0, 2, 1].into()));
+
+        let parts = jarm.retrieve_parts().unwrap();
+
+        assert_eq!(parts.len(), 10);
+        assert!(parts.iter().all(|part| part.to_string() == "c02b|0303|h2|0000-0017-ff01-000b-0023-0010"));
+        jarm.parts = parts;
+        assert_eq!(jarm.hash().unwrap(), SERVER_HELLO_HASH);
+    }
+
+    #[test]
+    fn test_transport_receives_every_client_hello() {
+        let transport = Arc::new(CannedTransport::new(server_hello()));
+        let jarm = canned_jarm(transport.clone(), StaticResolver::new().with_host("jarm.example", [192, 0, 2, 1].into()));
+
+        jarm.report();
+
+        let client_hellos = transport.client_hellos.lock().unwrap();
+        assert_eq!(client_hellos.len(), 10);
+        for (address, client_hello) in client_hellos.iter() {
+            assert_eq!(*address, "192.0.2.1:443".parse().unwrap());
+            assert_eq!(client_hello[0], 0x16);  // handshake record
+            assert!(client_hello.windows(12).any(|window| window == b"jarm.example"));  // SNI
+        }
+    }
+
+    #[test]
+    fn test_transport_pins_first_address() {
+        let transport = Arc::new(CannedTransport::new(server_hello()));
+        let resolver = StaticResolver::new()
+            .with_host("jarm.example", [192, 0, 2, 1].into())
+            .with_host("jarm.example", [192, 0, 2, 2].into());
+        let jarm = canned_jarm(transport.clone(), resolver);
+
+        let report = jarm.report();
+
+        assert_eq!(report.address, Some("192.0.2.1:443".parse().unwrap()));
+        // The connection opened to pick the address is used by the first probe
+        assert!(transport.client_hellos.lock().unwrap().iter().all(|(address, _)| *address == "192.0.2.1:443".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_transport_connection_error() {
+        let mut jarm = Jarm::new("jarm.example".to_string(), "443".to_string());
+        jarm.address = Some("192.0.2.1:443".parse().unwrap());
+        jarm.transport = Arc::new(RefusingTransport);
+
+        let report = jarm.report();
+
+        assert_eq!(report.probes.len(), 10);
+        for probe in &report.probes {
+            assert_eq!(probe.outcome, ProbeOutcome::Reset);
+            assert_eq!(probe.part, JarmPart::NoResponse);
+            assert!(matches!(probe.error, Some(JarmError::Connection(_))));
+        }
+    }
+}
@@ -0,0 +1,98 @@
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::net::SocketAddr;
+    use std::time::Duration;
+    use rust_jarm::error::JarmError;
+    use rust_jarm::scanner::Scanner;
+    use rust_jarm::sni::ServerName;
+    use rust_jarm::vhosts::ServerNameHashes;
+    use crate::common::{read_client_hello, server_hello, spawn_server, SERVER_HELLO_HASH};
+
+    const HANDSHAKE_FAILURE: [u8; 7] = [0x15, 0x03, 0x03, 0x00, 0x02, 0x02, 0x28];
+
+    fn contains(client_hello: &[u8], name: &[u8]) -> bool {
+        client_hello.windows(name.len()).any(|window| window == name)
+    }
+
+    /// Server hello selecting another cipher suite
+    fn other_server_hello() -> Vec<u8> {
+        let mut response = server_hello();
+        response[44..46].copy_from_slice(&[0xc0, 0x2f]);
+        response
+    }
+
+    /// Server with a TLS stack per SNI: shared for a.example and b.example, rejecting c.example,
+    /// and another one without SNI
+    fn spawn_virtual_hosts() -> SocketAddr {
+        spawn_server(|mut stream| {
+            let client_hello = read_client_hello(&mut stream);
+            let response = if contains(&client_hello, b"a.example") || contains(&client_hello, b"b.example") {
+                server_hello()
+            } else if contains(&client_hello, b"c.example") {
+                HANDSHAKE_FAILURE.to_vec()
+            } else {
+                other_server_hello()
+            };
+            stream.write_all(&response).unwrap();
+        })
+    }
+
+    fn name(name: &str) -> ServerName {
+        ServerName::Name(name.to_string())
+    }
+
+    fn scan(address: SocketAddr) -> ServerNameHashes {
+        let mut scanner = Scanner::new(2);
+        scanner.timeout = Duration::from_secs(2);
+        scanner.scan_server_names(address, vec![name("a.example"), name("b.example"), name("c.example"), ServerName::Omitted])
+    }
+
+    #[test]
+    fn test_scan_server_names_keeps_order() {
+        let hashes = scan(spawn_virtual_hosts());
+
+        let server_names: Vec<_> = hashes.hashes.iter().map(|(server_name, _)| server_name.clone()).collect();
+        assert_eq!(server_names, vec![name("a.example"), name("b.example"), name("c.example"), ServerName::Omitted]);
+        assert_eq!(hashes.hashes[0].1.as_deref().unwrap(), SERVER_HELLO_HASH);
+        assert_eq!(hashes.hashes[2].1.as_deref().unwrap(), "0".repeat(62));
+    }
+
+    #[test]
+    fn test_scan_server_names_groups() {
+        let hashes = scan(spawn_virtual_hosts());
+
+        let groups = hashes.groups();
+
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[SERVER_HELLO_HASH], vec![&name("a.example"), &name("b.example")]);
+        assert_eq!(hashes.unique(), vec![&name("c.example"), &ServerName::Omitted]);
+    }
+
+    #[test]
+    fn test_scan_server_names_invalid_name() {
+        let address = spawn_virtual_hosts();
+        let hashes = Scanner::new(2).scan_server_names(address, vec![name("a.example"), name("foo bar")]);
+
+        assert_eq!(hashes.hashes.len(), 2);
+        assert_eq!(hashes.hashes[0].1.as_deref().unwrap(), SERVER_HELLO_HASH);
+        assert!(matches!(hashes.hashes[1], (ServerName::Name(_), Err(JarmError::InvalidTarget { .. }))));
+    }
+
+    #[test]
+    fn test_scan_server_names_failures_left_out() {
+        let closed = {
+            let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+            listener.local_addr().unwrap()
+        };
+
+        let hashes = scan(closed);
+
+        assert_eq!(hashes.hashes.len(), 4);
+        assert!(hashes.hashes.iter().all(|(_, hash)| hash.is_err()));
+        assert!(hashes.groups().is_empty());
+        assert!(hashes.unique().is_empty());
+    }
+}